@@ -1,4 +1,5 @@
-use gstd::{msg, prelude::*, ActorId, CodeId};
+use gear_test_runtime::Session;
+use gstd::{msg, prelude::*, sync::RwLock, ActorId, CodeId};
 
 #[gstd::async_main]
 async fn main() {
@@ -17,11 +18,19 @@ async fn create_this(code_hash: &CodeId) -> ActorId {
     actor_id
 }
 
-#[gear_test_codegen::test]
-async fn good(context: &gear_test_runtime::SessionData) {
-    let this = create_this(&context.testee()).await;
+#[gear_test_codegen::before_all]
+fn testee_is_supplied_to_suite(session: &Session) {
+    gear_test_runtime::assert_ne!(session.testee(), CodeId::default());
+}
 
-    let result: Vec<u8> = msg::send_bytes_for_reply(this, b"PING", 0, 0)
+#[gear_test_codegen::fixture]
+async fn pinger(session: &Session) -> ActorId {
+    create_this(&session.testee()).await
+}
+
+#[gear_test_codegen::test]
+async fn good(_session: &Session, pinger: ActorId) {
+    let result: Vec<u8> = msg::send_bytes_for_reply(pinger, b"PING", 0, 0)
         .expect("failed to send")
         .await
         .expect("Program to handle simple PING!!1");
@@ -30,13 +39,166 @@ async fn good(context: &gear_test_runtime::SessionData) {
 }
 
 #[gear_test_codegen::test]
-async fn bad(context: &gear_test_runtime::SessionData) {
-    let this = create_this(&context.testee()).await;
+async fn bad(session: &Session) {
+    let this = create_this(&session.testee()).await;
 
     let result: Vec<u8> = msg::send_bytes_for_reply(this, b"PING", 0, 0)
         .expect("failed to send")
         .await
         .expect("Program to handle simple PING!!1");
+    gear_test_runtime::log!("PING replied with {:?}", result);
+
+    gear_test_runtime::assert_eq!(result, b"NOTPOING", "reply to PING");
+}
+
+#[gear_test_codegen::test]
+fn testee_is_supplied(session: &Session) {
+    assert_ne!(session.testee(), CodeId::default());
+}
+
+#[gear_test_codegen::test(init = ())]
+async fn testee_is_initialized(session: &Session) {
+    // fixed-size arrays are encoded as is, so raw PING/PONG can be sent as typed messages
+    let result: [u8; 4] = session.send(session.actor(), *b"PING", 0).await;
+
+    gear_test_runtime::assert_eq!(&result, b"PONG");
+}
+
+#[gear_test_codegen::test]
+async fn fresh_instance_is_created(session: &Session) {
+    let first = session.create_program(session.testee(), (), 0).await;
+    let second = session.create_program(session.testee(), (), 0).await;
+    gear_test_runtime::assert_ne!(first, second);
+
+    let result: [u8; 4] = session.send(second, *b"PING", 0).await;
+    gear_test_runtime::assert_eq!(&result, b"PONG");
+}
+
+#[gear_test_codegen::test(init = ())]
+async fn stubbed_request_is_not_forwarded(session: &Session) {
+    let mock = session.interpose(session.actor()).await;
+    mock.stub(*b"PANG", *b"STUB").await;
+
+    let stubbed: [u8; 4] = session.send(mock.actor(), *b"PANG", 0).await;
+    gear_test_runtime::assert_eq!(&stubbed, b"STUB");
+
+    let forwarded: [u8; 4] = session.send(mock.actor(), *b"PING", 0).await;
+    gear_test_runtime::assert_eq!(&forwarded, b"PONG");
+}
+
+const VALUE: u128 = 10_000_000_000_000;
+
+#[gear_test_codegen::test(init = ())]
+async fn value_is_kept_by_testee(session: &Session) {
+    let (result, change) = session
+        .balance_change(session.send::<_, [u8; 4]>(session.actor(), *b"PING", VALUE))
+        .await;
+
+    gear_test_runtime::assert_eq!(&result, b"PONG");
+    gear_test_runtime::assert_eq!(change, -(VALUE as i128), "value attached to PING");
+}
+
+#[gear_test_codegen::test]
+async fn wallet_keeps_transferred_value(session: &Session) {
+    let wallet = session.wallet().await;
+    let before = session.balance_of(wallet).await;
+
+    // balance query is handled after the transfer, messages are delivered in order
+    session.transfer(wallet, VALUE);
+    session.assert_balance(wallet, before + VALUE).await;
+}
+
+#[gear_test_codegen::test(should_fail = "not a PING")]
+async fn only_ping_is_answered(session: &Session) {
+    let this = create_this(&session.testee()).await;
+
+    let result: Vec<u8> = msg::send_bytes_for_reply(this, b"PANG", 0, 0)
+        .expect("failed to send")
+        .await
+        .expect("Program to handle unknown command");
+
+    assert_eq!(result, b"PONG", "not a PING");
+}
 
-    assert_eq!(result, b"NOTPOING")
+#[gear_test_codegen::test(ignore = "not implemented yet")]
+async fn pong(_session: &Session) {
+    unimplemented!("PONG handling")
+}
+
+#[gear_test_codegen::bench]
+async fn ping(session: &Session) {
+    let this = create_this(&session.testee()).await;
+
+    msg::send_bytes_for_reply(this, b"PING", 0, 0)
+        .expect("failed to send")
+        .await
+        .expect("Program to handle simple PING!!1");
+}
+
+#[gear_test_codegen::test]
+async fn out_of_gas(_session: &Session) {
+    let mut counter = 0u64;
+    loop {
+        counter = core::hint::black_box(counter.wrapping_add(1));
+    }
+}
+
+// tests torn down so far, teardown of the failed test is executed in a separate message,
+// so it is kept in the program memory
+static TORN_DOWN: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+#[gear_test_codegen::teardown]
+async fn remember_torn_down(session: &Session) {
+    TORN_DOWN.write().await.push(session.test_name());
+}
+
+#[gear_test_codegen::test(should_fail = "before teardown")]
+fn fails_before_teardown(_session: &Session) {
+    panic!("before teardown");
+}
+
+#[gear_test_codegen::test]
+async fn failed_test_is_torn_down(_session: &Session) {
+    let torn_down = TORN_DOWN
+        .read()
+        .await
+        .iter()
+        .any(|name| name.ends_with("::fails_before_teardown"));
+
+    gear_test_runtime::assert!(torn_down, "teardown of the failed test");
+}
+
+#[gear_test_codegen::test]
+fn rng_is_reproducible(session: &Session) {
+    let mut rng = session.rng();
+    let values = [rng.next_u64(), rng.next_u64()];
+
+    gear_test_runtime::assert_ne!(values[0], values[1]);
+    gear_test_runtime::assert_eq!(session.rng().next_u64(), values[0]);
+}
+
+#[gear_test_codegen::test]
+fn reads_host_fixture(session: &Session) {
+    // supplied only by the harness test of `ControlClient`
+    if let Some(answer) = session.host_fixture::<u32>("answer") {
+        gear_test_runtime::assert_eq!(answer, 42);
+    }
+}
+
+#[gear_test_codegen::proptest(cases = 16)]
+fn addition_commutes(_session: &Session, a: u32, b: u32) {
+    assert_eq!(a.wrapping_add(b), b.wrapping_add(a));
+}
+
+#[gear_test_codegen::proptest(should_fail = "minimal counterexample: (10,)")]
+fn below_ten(_session: &Session, value: u8) {
+    assert!(value < 10, "too big");
+}
+
+gear_test_codegen::test_matrix! {
+    [(1, 2), (2, 4), (21, 42)];
+    #[test(tags("arithmetic"))]
+    fn doubles(_session: &Session, input: u32, expected: u32) {
+        gear_test_runtime::assert_eq!(input * 2, expected);
+    }
 }
@@ -1,3 +1,4 @@
+use crate::manifest::{self, ExtractionManifest, MANIFEST_SECTION};
 use anyhow::{bail, Context as _, Result};
 use wasm_graph::{
     DataSegment, ElementSegment, EntryRef, ExportLocal, Func, ImportedOrDeclared, Instruction,
@@ -13,14 +14,16 @@ impl Context {
         Self { module }
     }
 
-    pub fn test_funcs(&self) -> Vec<EntryRef<Func>> {
+    fn test_exports(&self) -> Vec<(String, EntryRef<Func>)> {
         self.module
             .exports
             .iter()
             .filter_map(|export| {
                 if export.name.starts_with("test_") {
                     match export.local {
-                        ExportLocal::Func(ref func_ref) => Some(func_ref.clone()),
+                        ExportLocal::Func(ref func_ref) => {
+                            Some((export.name.clone(), func_ref.clone()))
+                        }
                         _ => None,
                     }
                 } else {
@@ -30,6 +33,18 @@ impl Context {
             .collect()
     }
 
+    pub fn test_funcs(&self) -> Vec<EntryRef<Func>> {
+        self.test_exports()
+            .into_iter()
+            .map(|(_, func_ref)| func_ref)
+            .collect()
+    }
+
+    /// Manifest of the test table, in the same order as `test_funcs`
+    pub fn manifest(&self) -> ExtractionManifest {
+        ExtractionManifest::new(self.test_exports().into_iter().map(|(name, _)| name))
+    }
+
     pub fn default_memory(&self) -> Result<EntryRef<Memory>> {
         match self.module.memory.get(0) {
             None => {
@@ -170,10 +185,23 @@ impl Context {
     }
 }
 
-pub fn extract(module: parity_wasm::elements::Module) -> Result<parity_wasm::elements::Module> {
+pub fn extract(
+    module: parity_wasm::elements::Module,
+) -> Result<(parity_wasm::elements::Module, ExtractionManifest)> {
+    let section = module
+        .custom_sections()
+        .find(|section| section.name() == MANIFEST_SECTION)
+        .cloned();
+    let records = match section {
+        Some(ref section) => manifest::parse_section(section.payload())
+            .with_context(|| "Unable to parse test manifest section")?,
+        None => Vec::new(),
+    };
+
     let module = Module::from_elements(&module).with_context(|| "Unable to parse module")?;
     let mut context = Context::new(module);
 
+    let manifest = context.manifest().with_records(&records);
     let test_funcs = context.test_funcs();
     let handle_impl = context.handle_impl()?;
     let run_tests_impl = context.run_tests_impl()?;
@@ -204,7 +232,16 @@ pub fn extract(module: parity_wasm::elements::Module) -> Result<parity_wasm::ele
         .exports
         .retain(|export| !(export.name.starts_with("test_") || export.name == "run_tests"));
 
-    let result = module.generate()?;
+    let mut result = module.generate()?;
+
+    // keep the manifest section, so that tests can be listed out of the extracted wasm too
+    if let Some(section) = section {
+        if result.custom_sections().all(|s| s.name() != MANIFEST_SECTION) {
+            result
+                .sections_mut()
+                .push(parity_wasm::elements::Section::Custom(section));
+        }
+    }
 
-    Ok(result)
+    Ok((result, manifest))
 }
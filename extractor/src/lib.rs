@@ -22,17 +22,33 @@
 mod tests;
 
 mod graph;
+mod manifest;
 
 use anyhow::Result;
 use parity_wasm::elements::{Deserialize, Module, Serialize};
 
+pub use manifest::{
+    parse_section, ExtractionManifest, ManifestEntry, SectionRecord, MANIFEST_SCHEMA_VERSION,
+    MANIFEST_SECTION,
+};
+
 pub fn extract_from_bytes(bytes: &[u8]) -> Result<Vec<u8>> {
+    extract_from_bytes_with_manifest(bytes).map(|(data, _)| data)
+}
+
+pub fn extract_from_bytes_with_manifest(bytes: &[u8]) -> Result<(Vec<u8>, ExtractionManifest)> {
     let module = parity_wasm::elements::Module::deserialize(&mut &bytes[..])?;
+    let (module, manifest) = extract_with_manifest(module)?;
     let mut data = Vec::new();
-    parity_wasm::elements::Module::serialize(extract(module)?, &mut data)?;
-    Ok(data)
+    parity_wasm::elements::Module::serialize(module, &mut data)?;
+    Ok((data, manifest))
 }
 
 pub fn extract(module: Module) -> Result<Module> {
+    extract_with_manifest(module).map(|(module, _)| module)
+}
+
+/// Same as `extract`, but also returns the manifest of the extracted test table
+pub fn extract_with_manifest(module: Module) -> Result<(Module, ExtractionManifest)> {
     graph::extract(module)
 }
@@ -0,0 +1,157 @@
+//! Metadata about the extracted test harness
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// Version of the manifest layout, bump on incompatible changes.
+pub const MANIFEST_SCHEMA_VERSION: u32 = 1;
+
+/// Custom section with the records emitted by the codegen macros
+/// (see `gear_test_runtime::manifest`).
+pub const MANIFEST_SECTION: &str = "gtest-manifest";
+
+/// Single test found in the module.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Position in the test table, same index the runtime reports in progress signals
+    pub index: u32,
+    /// Export name of the test entry (`test_*`)
+    pub export: String,
+    /// Full name of the test, if recorded in the manifest section
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Kind of the entry (`test`, `bench`, `setup`, ...), if recorded in the manifest section
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// Record of the `gtest-manifest` custom section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionRecord {
+    pub export: String,
+    pub name: String,
+    pub kind: u8,
+    pub doc: String,
+    pub tags: Vec<String>,
+}
+
+/// Name of the `gear_test_runtime::TestKind` stored in the record.
+fn kind_name(kind: u8) -> &'static str {
+    match kind {
+        0 => "test",
+        1 => "bench",
+        2 => "setup",
+        3 => "teardown",
+        4 => "before_all",
+        5 => "after_all",
+        _ => "unknown",
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() < len {
+            bail!("Manifest section is truncated");
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.u32()? as usize;
+        Ok(String::from_utf8(self.take(len)?.to_vec())?)
+    }
+}
+
+/// Parse concatenated records of the manifest section.
+pub fn parse_section(payload: &[u8]) -> Result<Vec<SectionRecord>> {
+    let mut reader = Reader { data: payload };
+    let mut records = Vec::new();
+
+    while !reader.data.is_empty() {
+        let export = reader.string()?;
+        let name = reader.string()?;
+        let kind = reader.u8()?;
+        let doc = reader.string()?;
+        let tags = (0..reader.u32()?)
+            .map(|_| reader.string())
+            .collect::<Result<_>>()?;
+
+        records.push(SectionRecord {
+            export,
+            name,
+            kind,
+            doc,
+            tags,
+        });
+    }
+
+    Ok(records)
+}
+
+/// Manifest produced alongside the rewritten wasm.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractionManifest {
+    pub schema_version: u32,
+    pub tests: Vec<ManifestEntry>,
+}
+
+impl ExtractionManifest {
+    pub fn new(exports: impl IntoIterator<Item = String>) -> Self {
+        ExtractionManifest {
+            schema_version: MANIFEST_SCHEMA_VERSION,
+            tests: exports
+                .into_iter()
+                .enumerate()
+                .map(|(index, export)| ManifestEntry {
+                    index: index as u32,
+                    export,
+                    name: None,
+                    kind: None,
+                    doc: None,
+                    tags: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Fill entries with the data of the manifest section records (matched by export).
+    pub fn with_records(mut self, records: &[SectionRecord]) -> Self {
+        for entry in self.tests.iter_mut() {
+            if let Some(record) = records.iter().find(|record| record.export == entry.export) {
+                entry.name = Some(record.name.clone());
+                entry.kind = Some(kind_name(record.kind).to_string());
+                entry.doc = Some(record.doc.clone()).filter(|doc| !doc.is_empty());
+                entry.tags = record.tags.clone();
+            }
+        }
+        self
+    }
+
+    pub fn test_count(&self) -> usize {
+        self.tests.len()
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
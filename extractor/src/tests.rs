@@ -91,3 +91,133 @@ fn simple() {
 
     assert_bytes(&actual_bytes[..], &expected_bytes[..]);
 }
+
+#[test]
+fn manifest() {
+    let original_bytes = bytes(
+        r#"
+        (module
+            (type (;0;) (func))
+            (type (;1;) (func (param i32)))
+            (import "env" "memory" (memory 1))
+            (table 1 1 funcref)
+            (export "handle" (func 0))
+            (export "run_tests" (func 3))
+            (export "test_some_test" (func 1))
+            (export "test_another_test" (func 2))
+            (func (;0;))
+            (func (;1;))
+            (func (;2;))
+            (func (type 1) (;3;))
+        )
+    "#,
+    );
+
+    let (_, manifest) = super::extract_from_bytes_with_manifest(&original_bytes[..])
+        .expect("Failed to extract");
+
+    assert_eq!(manifest.schema_version, super::MANIFEST_SCHEMA_VERSION);
+    assert_eq!(manifest.test_count(), 2);
+    assert_eq!(
+        manifest
+            .tests
+            .iter()
+            .map(|entry| (entry.index, entry.export.as_str()))
+            .collect::<Vec<_>>(),
+        vec![(0, "test_some_test"), (1, "test_another_test")]
+    );
+}
+
+/// Append custom section to the encoded module.
+fn with_custom_section(mut module: Vec<u8>, name: &str, payload: &[u8]) -> Vec<u8> {
+    fn leb(mut value: usize, out: &mut Vec<u8>) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                out.push(byte);
+                break;
+            }
+            out.push(byte | 0x80);
+        }
+    }
+
+    let mut content = Vec::new();
+    leb(name.len(), &mut content);
+    content.extend(name.as_bytes());
+    content.extend(payload);
+
+    module.push(0);
+    leb(content.len(), &mut module);
+    module.extend(content);
+    module
+}
+
+/// Record encoded by the runtime, the same way the codegen macros place it into the section.
+macro_rules! record {
+    ($export:expr, $name:expr, $kind:expr, $doc:expr, $tags:expr) => {{
+        use gear_test_runtime::manifest;
+
+        const RECORD: [u8; manifest::record_len($export, $name, $doc, $tags)] =
+            manifest::record($export, $name, $kind, $doc, $tags);
+        RECORD.to_vec()
+    }};
+}
+
+#[test]
+fn manifest_section() {
+    let module = bytes(
+        r#"
+        (module
+            (type (;0;) (func))
+            (type (;1;) (func (param i32)))
+            (import "env" "memory" (memory 1))
+            (table 1 1 funcref)
+            (export "handle" (func 0))
+            (export "run_tests" (func 3))
+            (export "test_some_test" (func 1))
+            (export "test_bench_test" (func 2))
+            (func (;0;))
+            (func (;1;))
+            (func (;2;))
+            (func (type 1) (;3;))
+        )
+    "#,
+    );
+    let mut payload = record!("test_bench_test", "example::bench_test", 1, "", &[]);
+    payload.extend(record!(
+        "test_some_test",
+        "example::some_test",
+        0,
+        "Checks something.",
+        &["slow", "smoke"]
+    ));
+    let original_bytes =
+        with_custom_section(module, gear_test_runtime::manifest::SECTION, &payload);
+
+    let (actual_bytes, manifest) = super::extract_from_bytes_with_manifest(&original_bytes[..])
+        .expect("Failed to extract");
+
+    let some_test = &manifest.tests[0];
+    assert_eq!(some_test.export, "test_some_test");
+    assert_eq!(some_test.name.as_deref(), Some("example::some_test"));
+    assert_eq!(some_test.kind.as_deref(), Some("test"));
+    assert_eq!(some_test.doc.as_deref(), Some("Checks something."));
+    assert_eq!(some_test.tags, vec!["slow", "smoke"]);
+
+    let bench_test = &manifest.tests[1];
+    assert_eq!(bench_test.kind.as_deref(), Some("bench"));
+    assert_eq!(bench_test.doc, None);
+
+    // section is kept in the extracted wasm
+    let module: parity_wasm::elements::Module =
+        parity_wasm::deserialize_buffer(&actual_bytes).expect("Failed to parse extracted wasm");
+    let section = module
+        .custom_sections()
+        .find(|section| section.name() == super::MANIFEST_SECTION)
+        .expect("Manifest section should be kept");
+    assert_eq!(
+        super::parse_section(section.payload()).expect("Failed to parse section"),
+        super::parse_section(&payload).expect("Failed to parse section")
+    );
+}
@@ -1,10 +1,11 @@
 use anyhow::{Context, Result};
 use gear_core::code::ALLOWED_EXPORTS;
 use gear_wasm_builder::{PreProcessorTarget, WasmBuilder};
-use pwasm_utils::parity_wasm::elements::{Module, Serialize as _};
+use pwasm_utils::parity_wasm::elements::{Module, Section, Serialize as _};
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
+use wasm_test_extractor::{ExtractionManifest, MANIFEST_SECTION};
 
 struct TestBinaryPreProcessor;
 
@@ -20,6 +21,16 @@ fn clone_and_opt(original_module: &Module) -> Result<Vec<u8>> {
     Ok(code)
 }
 
+/// Module without the test manifest section, which is only needed by the test runner.
+fn without_manifest(original_module: &Module) -> Module {
+    let mut module = original_module.clone();
+    module.sections_mut().retain(|section| match section {
+        Section::Custom(section) => section.name() != MANIFEST_SECTION,
+        _ => true,
+    });
+    module
+}
+
 fn create_and_write_to_file(path: PathBuf, data: &[u8]) -> std::io::Result<()> {
     let mut file = File::create(path)?;
     file.write_all(data)?;
@@ -39,6 +50,18 @@ fn create_artifact_path_and_write(
     Ok(artifact_path)
 }
 
+fn create_manifest_path_and_write(
+    absolute_path: &PathBuf,
+    stem: &str,
+    manifest: &ExtractionManifest,
+) -> Result<PathBuf> {
+    let mut manifest_path = PathBuf::from(absolute_path.clone());
+    manifest_path.pop();
+    manifest_path.push(format!("{}.test.manifest.json", stem));
+    create_and_write_to_file(manifest_path.clone(), manifest.to_json()?.as_bytes())?;
+    Ok(manifest_path)
+}
+
 impl gear_wasm_builder::PreProcessor for TestBinaryPreProcessor {
     fn name(&self) -> &'static str {
         "test"
@@ -64,9 +87,10 @@ impl gear_wasm_builder::PreProcessor for TestBinaryPreProcessor {
                 )
             })?;
 
-        let original_code = clone_and_opt(&original_module)?;
+        let original_code = clone_and_opt(&without_manifest(&original_module))?;
 
-        let module_with_test_runner = wasm_test_extractor::extract(original_module)?;
+        let (module_with_test_runner, manifest) =
+            wasm_test_extractor::extract_with_manifest(original_module)?;
 
         let code_with_test_runner = clone_and_opt(&module_with_test_runner)?;
 
@@ -90,6 +114,7 @@ impl gear_wasm_builder::PreProcessor for TestBinaryPreProcessor {
                 "test.opt",
                 &code_with_test_runner[..],
             )?;
+            create_manifest_path_and_write(&absolute_path, stem.as_ref(), &manifest)?;
 
             let record = format!(
                 "{}|{}",
@@ -170,4 +195,31 @@ mod tests {
         );
 
     }
+
+    #[test]
+    fn manifest_is_kept_for_tests_only() {
+        let manifest_section = |path: &str| {
+            let module = pwasm_utils::parity_wasm::deserialize_file(path)
+                .unwrap_or_else(|e| panic!("Example should be built ({}): {:?}", path, e));
+            module
+                .custom_sections()
+                .find(|section| section.name() == MANIFEST_SECTION)
+                .map(|section| section.payload().to_vec())
+        };
+
+        // section survives optimization of the test wasm, but not of the program under test
+        let payload =
+            manifest_section("../target/wasm32-unknown-unknown/debug/example_test.opt.wasm")
+                .expect("Manifest section should be kept in the test wasm");
+        let records =
+            wasm_test_extractor::parse_section(&payload).expect("Failed to parse section");
+        assert!(records
+            .iter()
+            .any(|record| record.name.ends_with("::doubles::case_3")));
+
+        assert_eq!(
+            manifest_section("../target/wasm32-unknown-unknown/debug/example.opt.wasm"),
+            None
+        );
+    }
 }
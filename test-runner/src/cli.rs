@@ -0,0 +1,305 @@
+//! Command line handling: options consumed by the runner itself, the rest goes to cargo
+
+use gear_test_runtime::{RunMode, TestFilter};
+use std::path::PathBuf;
+
+/// Options understood by `cargo gtest` (not forwarded to `cargo build`).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RunnerOptions {
+    /// Fail the run if fewer tests than this were executed
+    pub min_tests: Option<u32>,
+    /// Run only tests with names containing this text
+    pub filter: Option<String>,
+    /// Run only tests having any of these tags
+    pub tags: Vec<String>,
+    /// Do not run tests having any of these tags
+    pub exclude_tags: Vec<String>,
+    /// Auxiliary codes tests can deploy by name
+    pub codes: Vec<(String, PathBuf)>,
+    /// Seed of the generated test inputs
+    pub seed: Option<u64>,
+    /// List tests instead of running them
+    pub list: bool,
+    /// Value minted to the test program
+    pub balance: Option<u128>,
+    /// Print gas burned by every test
+    pub gas_table: bool,
+    /// Saved gas table of a previous run, to flag tests burning more gas
+    pub gas_baseline: Option<PathBuf>,
+    /// Print lines logged by tests as they come
+    pub nocapture: bool,
+    /// Number of tests executed at the same time
+    pub test_threads: Option<u32>,
+}
+
+fn option_value(
+    name: &str,
+    arg: &str,
+    rest: &mut impl Iterator<Item = String>,
+) -> anyhow::Result<Option<String>> {
+    if arg == name {
+        match rest.next() {
+            Some(value) => Ok(Some(value)),
+            None => anyhow::bail!("Option {} requires a value", name),
+        }
+    } else {
+        Ok(arg
+            .strip_prefix(name)
+            .and_then(|tail| tail.strip_prefix('='))
+            .map(ToString::to_string))
+    }
+}
+
+impl RunnerOptions {
+    /// Split command line into runner options and arguments for cargo
+    pub fn parse(
+        args: impl IntoIterator<Item = String>,
+    ) -> anyhow::Result<(RunnerOptions, Vec<String>)> {
+        let mut options = RunnerOptions::default();
+        let mut cargo_args = Vec::new();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--list" {
+                options.list = true;
+            } else if arg == "--gas-table" {
+                options.gas_table = true;
+            } else if arg == "--nocapture" {
+                options.nocapture = true;
+            } else if let Some(value) = option_value("--min-tests", &arg, &mut args)? {
+                options.min_tests = Some(value.parse().map_err(|_| {
+                    anyhow::anyhow!("Invalid value for --min-tests: '{}'", value)
+                })?);
+            } else if let Some(value) = option_value("--filter", &arg, &mut args)? {
+                options.filter = Some(value);
+            } else if let Some(value) = option_value("--tag", &arg, &mut args)? {
+                options.tags.push(value);
+            } else if let Some(value) = option_value("--exclude-tag", &arg, &mut args)? {
+                options.exclude_tags.push(value);
+            } else if let Some(value) = option_value("--code", &arg, &mut args)? {
+                let (name, path) = value.split_once('=').ok_or_else(|| {
+                    anyhow::anyhow!("Invalid value for --code: '{}', expected NAME=PATH", value)
+                })?;
+                options.codes.push((name.to_string(), PathBuf::from(path)));
+            } else if let Some(value) = option_value("--seed", &arg, &mut args)? {
+                options.seed = Some(
+                    value
+                        .parse()
+                        .map_err(|_| anyhow::anyhow!("Invalid value for --seed: '{}'", value))?,
+                );
+            } else if let Some(value) = option_value("--balance", &arg, &mut args)? {
+                options.balance = Some(value.parse().map_err(|_| {
+                    anyhow::anyhow!("Invalid value for --balance: '{}'", value)
+                })?);
+            } else if let Some(value) = option_value("--test-threads", &arg, &mut args)? {
+                options.test_threads = Some(value.parse().map_err(|_| {
+                    anyhow::anyhow!("Invalid value for --test-threads: '{}'", value)
+                })?);
+            } else if let Some(value) = option_value("--gas-baseline", &arg, &mut args)? {
+                options.gas_baseline = Some(PathBuf::from(value));
+            } else {
+                cargo_args.push(arg);
+            }
+        }
+
+        Ok((options, cargo_args))
+    }
+
+    pub fn test_filter(&self) -> Option<TestFilter> {
+        let mut filters = Vec::new();
+        if let Some(ref filter) = self.filter {
+            filters.push(TestFilter::Contains(filter.clone()));
+        }
+        if !self.tags.is_empty() || !self.exclude_tags.is_empty() {
+            filters.push(TestFilter::Tags {
+                include: self.tags.clone(),
+                exclude: self.exclude_tags.clone(),
+            });
+        }
+
+        match filters.len() {
+            0 => None,
+            1 => filters.pop(),
+            _ => Some(TestFilter::All(filters)),
+        }
+    }
+
+    /// How tests are executed, one by one unless more test threads are requested
+    pub fn run_mode(&self) -> RunMode {
+        match self.test_threads {
+            Some(threads) if threads > 1 => RunMode::Concurrent(threads),
+            _ => RunMode::Sequential,
+        }
+    }
+
+    /// Whether the gas table should be printed after the run
+    pub fn print_gas_table(&self) -> bool {
+        self.gas_table || self.gas_baseline.is_some()
+    }
+
+    /// Check that enough tests were run
+    pub fn check_test_count(&self, executed: u32) -> anyhow::Result<()> {
+        match self.min_tests {
+            Some(min_tests) if executed < min_tests => anyhow::bail!(
+                "Only {} test(s) executed, at least {} expected. Are test functions excluded from the build?",
+                executed,
+                min_tests
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn min_tests_is_not_forwarded() {
+        let (options, cargo_args) =
+            RunnerOptions::parse(args(&["--release", "--min-tests", "3", "-p", "example"]))
+                .expect("Failed to parse");
+
+        assert_eq!(options.min_tests, Some(3));
+        assert_eq!(cargo_args, args(&["--release", "-p", "example"]));
+
+        let (options, _) =
+            RunnerOptions::parse(args(&["--min-tests=5"])).expect("Failed to parse");
+        assert_eq!(options.min_tests, Some(5));
+    }
+
+    #[test]
+    fn filter_is_not_forwarded() {
+        let (options, cargo_args) =
+            RunnerOptions::parse(args(&["--filter", "transfer", "--release"]))
+                .expect("Failed to parse");
+
+        assert_eq!(options.filter.as_deref(), Some("transfer"));
+        assert_eq!(cargo_args, args(&["--release"]));
+    }
+
+    #[test]
+    fn tags_make_filter() {
+        let (options, cargo_args) = RunnerOptions::parse(args(&[
+            "--tag",
+            "smoke",
+            "--exclude-tag=slow",
+            "--tag=fast",
+        ]))
+        .expect("Failed to parse");
+
+        assert!(cargo_args.is_empty());
+        let filter = options.test_filter().expect("Filter should be set");
+        assert!(filter.matches("smoke_test", &["smoke"]));
+        assert!(filter.matches("fast_test", &["fast", "integration"]));
+        assert!(!filter.matches("slow_smoke_test", &["smoke", "slow"]));
+        assert!(!filter.matches("untagged_test", &[]));
+
+        assert!(RunnerOptions::default().test_filter().is_none());
+    }
+
+    #[test]
+    fn codes_are_named() {
+        let (options, _) =
+            RunnerOptions::parse(args(&["--code", "token=target/token.opt.wasm"]))
+                .expect("Failed to parse");
+
+        assert_eq!(
+            options.codes,
+            vec![("token".to_string(), PathBuf::from("target/token.opt.wasm"))]
+        );
+        assert!(RunnerOptions::parse(args(&["--code", "token"])).is_err());
+    }
+
+    #[test]
+    fn seed_is_not_forwarded() {
+        let (options, cargo_args) =
+            RunnerOptions::parse(args(&["--seed", "42", "--release"])).expect("Failed to parse");
+
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(cargo_args, args(&["--release"]));
+        assert!(RunnerOptions::parse(args(&["--seed=-1"])).is_err());
+    }
+
+    #[test]
+    fn balance_is_not_forwarded() {
+        let (options, cargo_args) =
+            RunnerOptions::parse(args(&["--balance=1000", "--release"])).expect("Failed to parse");
+
+        assert_eq!(options.balance, Some(1000));
+        assert_eq!(cargo_args, args(&["--release"]));
+        assert!(RunnerOptions::parse(args(&["--balance", "lots"])).is_err());
+    }
+
+    #[test]
+    fn gas_baseline_implies_table() {
+        let (options, cargo_args) =
+            RunnerOptions::parse(args(&["--gas-baseline", "gas.txt", "--release"]))
+                .expect("Failed to parse");
+
+        assert_eq!(options.gas_baseline, Some(PathBuf::from("gas.txt")));
+        assert!(options.print_gas_table());
+        assert_eq!(cargo_args, args(&["--release"]));
+
+        let (options, _) = RunnerOptions::parse(args(&["--gas-table"])).expect("Failed to parse");
+        assert!(options.print_gas_table());
+        assert!(!RunnerOptions::default().print_gas_table());
+    }
+
+    #[test]
+    fn nocapture_is_not_forwarded() {
+        let (options, cargo_args) =
+            RunnerOptions::parse(args(&["--nocapture", "--release"])).expect("Failed to parse");
+
+        assert!(options.nocapture);
+        assert_eq!(cargo_args, args(&["--release"]));
+    }
+
+    #[test]
+    fn test_threads_make_run_mode() {
+        let (options, cargo_args) =
+            RunnerOptions::parse(args(&["--test-threads", "4", "--release"]))
+                .expect("Failed to parse");
+
+        assert_eq!(options.run_mode(), RunMode::Concurrent(4));
+        assert_eq!(cargo_args, args(&["--release"]));
+
+        let (options, _) =
+            RunnerOptions::parse(args(&["--test-threads=1"])).expect("Failed to parse");
+        assert_eq!(options.run_mode(), RunMode::Sequential);
+        assert_eq!(RunnerOptions::default().run_mode(), RunMode::Sequential);
+        assert!(RunnerOptions::parse(args(&["--test-threads", "all"])).is_err());
+    }
+
+    #[test]
+    fn list_is_not_forwarded() {
+        let (options, cargo_args) =
+            RunnerOptions::parse(args(&["--list", "--release"])).expect("Failed to parse");
+
+        assert!(options.list);
+        assert_eq!(cargo_args, args(&["--release"]));
+        assert!(!RunnerOptions::default().list);
+    }
+
+    #[test]
+    fn min_tests_requires_number() {
+        assert!(RunnerOptions::parse(args(&["--min-tests"])).is_err());
+        assert!(RunnerOptions::parse(args(&["--min-tests", "many"])).is_err());
+    }
+
+    #[test]
+    fn too_few_tests_fail() {
+        let options = RunnerOptions {
+            min_tests: Some(2),
+            ..Default::default()
+        };
+
+        assert!(options.check_test_count(1).is_err());
+        assert!(options.check_test_count(2).is_ok());
+        assert!(RunnerOptions::default().check_test_count(0).is_ok());
+    }
+}
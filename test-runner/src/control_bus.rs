@@ -11,6 +11,8 @@ use gear_test_runtime::{ProgressSignal, TestInfo, TestUpdate};
 #[derive(Debug, Default)]
 pub struct ControlBus {
     running_state: Arc<RwLock<State>>,
+    /// Print logged lines as they come instead of only along with failures
+    nocapture: bool,
 }
 
 #[derive(Debug, Default)]
@@ -18,7 +20,27 @@ pub struct State {
     started: u32,
     failed: u32,
     succeded: u32,
+    skipped: u32,
     unfinished: Vec<String>,
+    failures: Vec<(String, String)>,
+    suite_failures: Vec<(String, String)>,
+    benches: Vec<(String, u64)>,
+    gas: Vec<(String, u64)>,
+    /// Logged lines of unfinished tests
+    logs: Vec<(String, String)>,
+    /// Logged lines of failed tests, printed along with the failure
+    failure_logs: Vec<(String, String)>,
+    finished: Option<Summary>,
+}
+
+/// Totals of the run, as reported by the test program once all tests are finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    /// Passed tests, along with benchmarks
+    pub passed: u32,
+    pub failed: u32,
+    pub skipped: u32,
+    pub gas_total: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -26,26 +48,82 @@ pub struct Report {
     pub total_started: u32,
     pub total_failed: u32,
     pub total_succeded: u32,
+    pub total_skipped: u32,
     pub unfinished: Vec<String>,
+    /// Failed tests along with failure hints
+    pub failures: Vec<(String, String)>,
+    /// Failed suite hooks (`before_all` / `after_all`) along with failure hints
+    pub suite_failures: Vec<(String, String)>,
+    /// Finished benchmarks along with gas burned
+    pub benches: Vec<(String, u64)>,
+    /// Finished tests (but benchmarks) along with gas burned
+    pub gas: Vec<(String, u64)>,
+    /// Lines logged by failed tests along with the test name (not captured under
+    /// `--nocapture`, printed as they come instead)
+    pub logs: Vec<(String, String)>,
+    /// Seed of the run, reproduces generated test inputs
+    pub seed: u64,
+    /// Totals reported by the test program, `None` if the run was not finished
+    pub finished: Option<Summary>,
 }
 
 impl State {
-    pub fn submit_fail(&mut self, test_info: TestInfo) {
+    pub fn submit_fail(&mut self, test_info: TestInfo, hint: String) {
         self.failed += 1;
+        self.failures.push((test_info.name.clone(), hint));
         self.remove(test_info);
     }
 
+    pub fn submit_suite_fail(&mut self, test_info: TestInfo, hint: String) {
+        self.suite_failures.push((test_info.name, hint));
+    }
+
     pub fn submit_start(&mut self, test_info: TestInfo) {
         self.started += 1;
         self.append(test_info);
     }
 
-    pub fn submit_success(&mut self, test_info: TestInfo) {
+    pub fn submit_success(&mut self, test_info: TestInfo, gas_used: u64) {
         self.succeded += 1;
+        self.submit_gas(&test_info, gas_used);
         self.remove(test_info);
     }
 
+    pub fn submit_gas(&mut self, test_info: &TestInfo, gas_used: u64) {
+        self.gas.push((test_info.name.clone(), gas_used));
+    }
+
+    pub fn submit_skipped(&mut self) {
+        self.skipped += 1;
+    }
+
+    pub fn submit_bench(&mut self, test_info: TestInfo, gas: u64) {
+        self.benches.push((test_info.name.clone(), gas));
+        self.remove(test_info);
+    }
+
+    pub fn submit_finished(&mut self, summary: Summary) {
+        self.finished = Some(summary);
+    }
+
+    pub fn submit_log(&mut self, test_info: TestInfo, line: String) {
+        self.logs.push((test_info.name, line));
+    }
+
+    /// Logged lines of the failed test, kept for the report.
+    pub fn take_logs(&mut self, test_info: &TestInfo) -> Vec<String> {
+        let (logs, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut self.logs)
+            .into_iter()
+            .partition(|(name, _)| *name == test_info.name);
+        self.logs = rest;
+        self.failure_logs.extend(logs.iter().cloned());
+
+        logs.into_iter().map(|(_, line)| line).collect()
+    }
+
     fn remove(&mut self, test_info: TestInfo) {
+        self.logs.retain(|(name, _)| *name != test_info.name);
+
         let pos = self.unfinished.iter().position(|e| *e == test_info.name);
         if let Some(pos) = pos {
             self.unfinished.swap_remove(pos);
@@ -56,43 +134,91 @@ impl State {
         self.unfinished.push(test_info.name)
     }
 
-    pub fn report(&self) -> Report {
+    pub fn report(&self, seed: u64) -> Report {
         Report {
             total_started: self.started,
             total_succeded: self.succeded,
             total_failed: self.failed,
+            total_skipped: self.skipped,
             unfinished: self.unfinished.clone(),
+            failures: self.failures.clone(),
+            suite_failures: self.suite_failures.clone(),
+            benches: self.benches.clone(),
+            gas: self.gas.clone(),
+            logs: self.failure_logs.clone(),
+            seed,
+            finished: self.finished,
         }
     }
 }
 
 impl Report {
     pub fn success(&self) -> bool {
-        if self.unfinished.len() > 0 {
+        if self.unfinished.len() > 0 || self.signals_lost() {
             false
-        } else if self.total_failed != 0 {
+        } else if self.total_failed != 0 || !self.suite_failures.is_empty() {
             false
-        } else if self.total_started != self.total_succeded {
+        } else if self.total_started != self.total_succeded + self.benches.len() as u32 {
             false
         } else {
             true
         }
     }
+
+    /// Whether the run was not finished or the collected results disagree with the totals
+    /// reported by the test program (progress signals were lost)
+    pub fn signals_lost(&self) -> bool {
+        match self.finished {
+            Some(summary) => {
+                summary.passed != self.total_succeded + self.benches.len() as u32
+                    || summary.failed != self.total_failed
+                    || summary.skipped != self.total_skipped
+            }
+            None => true,
+        }
+    }
 }
 
 impl fmt::Display for Report {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(
             f,
-            "test result: {}. {} passed; {} failed;",
+            "test result: {}. {} passed; {} failed; {} ignored;",
             match self.success() {
                 true => "ok".green(),
                 false => "fail".red(),
             },
             self.total_succeded,
-            self.total_failed
+            self.total_failed,
+            self.total_skipped
         )?;
 
+        if !self.suite_failures.is_empty() {
+            writeln!(f, "failed suite hooks:")?;
+            for (name, hint) in self.suite_failures.iter() {
+                writeln!(f, "\t{}: {}", name, hint)?;
+            }
+        }
+
+        if !self.benches.is_empty() {
+            writeln!(f, "bench results:")?;
+            for (name, gas) in self.benches.iter() {
+                writeln!(f, "\t{} ... {} gas", name, gas)?;
+            }
+        }
+
+        match self.finished {
+            Some(summary) if !self.signals_lost() => {
+                writeln!(f, "gas burned by tests: {}", summary.gas_total)?
+            }
+            Some(summary) => writeln!(
+                f,
+                "progress signals were lost: test program reported {} passed; {} failed; {} ignored;",
+                summary.passed, summary.failed, summary.skipped
+            )?,
+            None => writeln!(f, "test run was not finished")?,
+        }
+
         if !self.unfinished.is_empty() {
             write!(f, "unfinished tests: [")?;
             for unfinished in self.unfinished.iter() {
@@ -101,6 +227,14 @@ impl fmt::Display for Report {
             writeln!(f, "]")?;
         }
 
+        if !self.success() {
+            writeln!(
+                f,
+                "seed: {} (rerun with `--seed {}` to reproduce)",
+                self.seed, self.seed
+            )?;
+        }
+
         Ok(())
     }
 }
@@ -109,6 +243,25 @@ impl ControlBus {
     pub fn running_state(&self) -> Arc<RwLock<State>> {
         self.running_state.clone()
     }
+
+    /// Print logged lines as they come (`--nocapture`)
+    pub fn nocapture(mut self, nocapture: bool) -> Self {
+        self.nocapture = nocapture;
+        self
+    }
+
+    /// Print captured lines logged by the failed test
+    fn print_logs(&self, test_info: &TestInfo) {
+        let logs = self.running_state.write().unwrap().take_logs(test_info);
+        if logs.is_empty() {
+            return;
+        }
+
+        println!("\t --- LOG @ {}", test_info.name);
+        for line in logs {
+            println!("{}", line);
+        }
+    }
 }
 
 impl WasmProgram for ControlBus {
@@ -127,19 +280,107 @@ impl WasmProgram for ControlBus {
             TestUpdate::Start => {
                 self.running_state.write().unwrap().submit_start(test_info);
             }
-            TestUpdate::Success => {
+            TestUpdate::Success { gas_used } => {
                 println!("test {} ... {}", test_info.name, "ok".green());
                 self.running_state
                     .write()
                     .unwrap()
-                    .submit_success(test_info);
+                    .submit_success(test_info, gas_used);
+            }
+            TestUpdate::Skipped(reason) => {
+                if reason.is_empty() {
+                    println!("test {} ... {}", test_info.name, "ignored".yellow());
+                } else {
+                    println!(
+                        "test {} ... {}, {}",
+                        test_info.name,
+                        "ignored".yellow(),
+                        reason
+                    );
+                }
+                self.running_state.write().unwrap().submit_skipped();
+            }
+            TestUpdate::BenchResult { gas } => {
+                println!("bench {} ... {} gas", test_info.name, gas);
+                self.running_state
+                    .write()
+                    .unwrap()
+                    .submit_bench(test_info, gas);
+            }
+            TestUpdate::Retry { attempt, hint } => {
+                println!(
+                    "test {} ... {} (attempt {} failed: {})",
+                    test_info.name,
+                    "retrying".yellow(),
+                    attempt,
+                    hint
+                );
+            }
+            TestUpdate::GasBudgetExceeded { budget, used } => {
+                println!("test {} ... {}", test_info.name, "fail".red());
+                let hint = format!("gas budget exceeded: used {} of {} gas", used, budget);
+                println!("\t{}", hint);
+                self.print_logs(&test_info);
+                let mut state = self.running_state.write().unwrap();
+                state.submit_gas(&test_info, used);
+                state.submit_fail(test_info, hint);
+            }
+            TestUpdate::AssertionFailed {
+                assertion,
+                gas_used,
+            } => {
+                println!("test {} ... {}", test_info.name, "fail".red());
+                println!("\t --- ASSERTION FAILED @ {}", test_info.name);
+                println!("{}", assertion);
+                self.print_logs(&test_info);
+                println!("\t --- END OF REPORT @ {}", test_info.name);
+                let mut state = self.running_state.write().unwrap();
+                state.submit_gas(&test_info, gas_used);
+                state.submit_fail(test_info, assertion.to_string());
+            }
+            TestUpdate::SuiteSetupFailed(hint) => {
+                println!("suite hook {} ... {}", test_info.name, "fail".red());
+                println!("\t{}", hint);
+                self.print_logs(&test_info);
+                self.running_state
+                    .write()
+                    .unwrap()
+                    .submit_suite_fail(test_info, hint);
+            }
+            TestUpdate::SuiteFinished {
+                passed,
+                failed,
+                skipped,
+                gas_total,
+            } => {
+                self.running_state
+                    .write()
+                    .unwrap()
+                    .submit_finished(Summary {
+                        passed,
+                        failed,
+                        skipped,
+                        gas_total,
+                    });
+            }
+            TestUpdate::Log(line) if self.nocapture => {
+                println!("{}: {}", test_info.name, line);
+            }
+            TestUpdate::Log(line) => {
+                self.running_state
+                    .write()
+                    .unwrap()
+                    .submit_log(test_info, line);
             }
-            TestUpdate::Fail(hint) => {
+            TestUpdate::Fail { hint, gas_used } => {
                 println!("test {} ... {}", test_info.name, "fail".red());
                 println!("\t --- ERROR REPORT @ {}", test_info.name);
                 println!("{}", hint);
+                self.print_logs(&test_info);
                 println!("\t --- END OF REPORT @ {}", test_info.name);
-                self.running_state.write().unwrap().submit_fail(test_info);
+                let mut state = self.running_state.write().unwrap();
+                state.submit_gas(&test_info, gas_used);
+                state.submit_fail(test_info, hint);
             }
         }
 
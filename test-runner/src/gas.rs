@@ -0,0 +1,146 @@
+//! Per-test gas table, compared with the table of a previous run to flag regressions
+use std::fmt::Write;
+
+use colored::Colorize;
+
+/// Header of the table written by `table`.
+const HEADER: &str = "gas usage:";
+
+/// Gas burned by tests, read from the saved output of a previous `--gas-table` run.
+///
+/// Only rows of the tables written by `table` (`\t<name> ... <gas> gas`, following the
+/// header) are taken, the rest of the output is skipped.
+pub fn parse_baseline(text: &str) -> Vec<(String, u64)> {
+    let mut in_table = false;
+
+    text.lines()
+        .filter_map(|line| {
+            if line == HEADER {
+                in_table = true;
+                return None;
+            }
+
+            let Some(row) = line.strip_prefix('\t').filter(|_| in_table) else {
+                in_table = false;
+                return None;
+            };
+
+            parse_row(row)
+        })
+        .collect()
+}
+
+/// Test name and gas out of the table row (without the leading tab).
+fn parse_row(row: &str) -> Option<(String, u64)> {
+    let (name, rest) = row.split_once(" ... ")?;
+    let (gas, tail) = rest.split_once(" gas")?;
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+    if !(tail.is_empty() || tail.starts_with(" (")) {
+        return None;
+    }
+
+    Some((name.to_string(), gas.parse().ok()?))
+}
+
+/// Table of gas burned by every test, tests burning more than in the `baseline` are flagged.
+pub fn table(gas: &[(String, u64)], baseline: &[(String, u64)]) -> String {
+    let mut table = format!("{}\n", HEADER);
+
+    for (name, used) in gas.iter() {
+        let previous = baseline
+            .iter()
+            .find(|(baseline_name, _)| baseline_name == name)
+            .map(|(_, gas)| *gas);
+
+        let _ = match previous {
+            Some(previous) if *used > previous => writeln!(
+                table,
+                "\t{} ... {} gas (+{}, {})",
+                name,
+                used,
+                used - previous,
+                "regression".red()
+            ),
+            Some(previous) if *used < previous => {
+                writeln!(table, "\t{} ... {} gas (-{})", name, used, previous - used)
+            }
+            _ => writeln!(table, "\t{} ... {} gas", name, used),
+        };
+    }
+
+    table
+}
+
+/// Tests burning more gas than in the `baseline`.
+pub fn regressions<'a>(gas: &'a [(String, u64)], baseline: &[(String, u64)]) -> Vec<&'a str> {
+    gas.iter()
+        .filter(|(name, used)| {
+            baseline
+                .iter()
+                .any(|(baseline_name, previous)| baseline_name == name && used > previous)
+        })
+        .map(|(name, _)| name.as_str())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gas(entries: &[(&str, u64)]) -> Vec<(String, u64)> {
+        entries
+            .iter()
+            .map(|(name, gas)| (name.to_string(), *gas))
+            .collect()
+    }
+
+    #[test]
+    fn table_is_baseline() {
+        colored::control::set_override(false);
+
+        let current = gas(&[("wasm::good", 1000), ("wasm::bad", 500)]);
+        let baseline = parse_baseline(&table(&current, &[]));
+
+        assert_eq!(baseline, current);
+        assert!(regressions(&current, &baseline).is_empty());
+    }
+
+    #[test]
+    fn baseline_is_read_from_runner_output() {
+        colored::control::set_override(false);
+
+        let current = gas(&[("wasm::good", 1000), ("wasm::bad", 500)]);
+        let output = format!(
+            "running 19 tests\n\
+             wasm::good: logged 42 times\n\
+             test wasm::good ... ok\n\
+             test wasm::bad ... fail\n\
+             \t --- ERROR REPORT @ wasm::bad\n\
+             bench results:\n\
+             \twasm::ping ... 300 gas\n\
+             test result: fail. 15 passed; 2 failed; 1 ignored;\n\
+             {}\
+             gas regressions: wasm::good\n",
+            table(&current, &[])
+        );
+
+        assert_eq!(parse_baseline(&output), current);
+    }
+
+    #[test]
+    fn regressions_are_flagged() {
+        colored::control::set_override(false);
+
+        let baseline = gas(&[("wasm::good", 1000), ("wasm::bad", 500)]);
+        let current = gas(&[("wasm::good", 1200), ("wasm::bad", 400), ("wasm::new", 10)]);
+
+        assert_eq!(regressions(&current, &baseline), vec!["wasm::good"]);
+
+        let table = table(&current, &baseline);
+        assert!(table.contains("wasm::good ... 1200 gas (+200, regression)"));
+        assert!(table.contains("wasm::bad ... 400 gas (-100)"));
+        assert!(table.contains("wasm::new ... 10 gas\n"));
+    }
+}
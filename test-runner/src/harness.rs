@@ -0,0 +1,482 @@
+//! Test harness wiring gtest `System`, the test program and the control bus together
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use codec::{Decode, Encode};
+use gear_test_runtime::{ControlSignal, RunMode, TestDescription, TestFilter};
+use gtest::{Program, System};
+
+use crate::control_bus::{self, Report};
+
+/// Value minted to the test program unless set by `TestHarnessBuilder::balance`.
+pub const DEFAULT_BALANCE: u128 = 1_000_000_000_000_000;
+
+/// Test program (extracted `_test.opt.wasm`) along with the code under test.
+///
+/// Every run starts from a fresh `System`, so runs never share state.
+#[derive(Debug, Clone)]
+pub struct TestHarness {
+    control_code: PathBuf,
+    under_test_code: PathBuf,
+    codes: Vec<(String, PathBuf)>,
+    seed: u64,
+    balance: u128,
+    nocapture: bool,
+    mode: RunMode,
+}
+
+#[derive(Debug, Default)]
+pub struct TestHarnessBuilder {
+    control_code: Option<PathBuf>,
+    under_test_code: Option<PathBuf>,
+    codes: Vec<(String, PathBuf)>,
+    seed: u64,
+    balance: Option<u128>,
+    nocapture: bool,
+    mode: RunMode,
+}
+
+impl TestHarnessBuilder {
+    /// Path to the wasm with the test runner (output of the extractor)
+    pub fn control_code(mut self, path: impl AsRef<Path>) -> Self {
+        self.control_code = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Path to the wasm of the program under test
+    pub fn under_test_code(mut self, path: impl AsRef<Path>) -> Self {
+        self.under_test_code = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Auxiliary code tests can deploy by `name`
+    pub fn code(mut self, name: impl Into<String>, path: impl AsRef<Path>) -> Self {
+        self.codes.push((name.into(), path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Seed of the generated test inputs (0 by default)
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Value minted to the test program, so that tests can attach it to messages
+    /// (`DEFAULT_BALANCE` by default)
+    pub fn balance(mut self, balance: u128) -> Self {
+        self.balance = Some(balance);
+        self
+    }
+
+    /// Print lines logged by tests as they come, not only along with failures
+    pub fn nocapture(mut self, nocapture: bool) -> Self {
+        self.nocapture = nocapture;
+        self
+    }
+
+    /// Execute tests one by one (default) or concurrently
+    pub fn mode(mut self, mode: RunMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn build(self) -> anyhow::Result<TestHarness> {
+        let control_code = self.control_code.context("Control code path is not set")?;
+        let under_test_code = self
+            .under_test_code
+            .context("Code under test path is not set")?;
+
+        let code_paths = self.codes.iter().map(|(_, path)| path);
+        for path in [&control_code, &under_test_code]
+            .into_iter()
+            .chain(code_paths)
+        {
+            if !path.exists() {
+                anyhow::bail!("Wasm file {:?} does not exist", path);
+            }
+        }
+
+        Ok(TestHarness {
+            control_code,
+            under_test_code,
+            codes: self.codes,
+            seed: self.seed,
+            balance: self.balance.unwrap_or(DEFAULT_BALANCE),
+            nocapture: self.nocapture,
+            mode: self.mode,
+        })
+    }
+}
+
+impl TestHarness {
+    pub fn builder() -> TestHarnessBuilder {
+        TestHarnessBuilder::default()
+    }
+
+    /// Client running tests with the values supplied to them by the host.
+    pub fn control_client(&self) -> ControlClient<'_> {
+        ControlClient {
+            harness: self,
+            fixtures: Vec::new(),
+        }
+    }
+
+    /// Run all tests and collect the report.
+    pub fn run(&self) -> anyhow::Result<Report> {
+        self.control_client().run()
+    }
+
+    /// Run only tests with the given full names (`module::test`) and collect the report.
+    pub fn run_filtered(&self, names: &[&str]) -> anyhow::Result<Report> {
+        self.control_client().run_filtered(names)
+    }
+
+    /// Run only tests matching the filter and collect the report.
+    pub fn run_matching(&self, filter: TestFilter) -> anyhow::Result<Report> {
+        self.control_client().run_matching(filter)
+    }
+
+    /// List tests (matching the filter, if any) without running them.
+    pub fn list(&self, filter: Option<TestFilter>) -> anyhow::Result<Vec<TestDescription>> {
+        let system = System::new();
+        let test_program = self.deploy_test_program(&system)?;
+
+        let res = test_program.send(0, ControlSignal::ListTests { filter });
+        if res.main_failed() {
+            anyhow::bail!("Failed to list tests of {:?}", self.control_code);
+        }
+
+        res.log()
+            .iter()
+            .find_map(|log| Vec::<TestDescription>::decode(&mut log.payload()).ok())
+            .context("Test program did not reply with the test list")
+    }
+
+    fn deploy_test_program<'a>(&self, system: &'a System) -> anyhow::Result<Program<'a>> {
+        let test_program = Program::from_file(system, &self.control_code);
+        let res = test_program.send_bytes(0, vec![]); // empty initialization for test program
+        if res.main_failed() {
+            anyhow::bail!("Failed to initialize test program {:?}", self.control_code);
+        }
+
+        Ok(test_program)
+    }
+}
+
+/// Runs tests of the harness, supplying them typed values (fixtures) by name.
+///
+/// Tests read the values with `Session::host_fixture`.
+#[derive(Debug, Clone)]
+pub struct ControlClient<'a> {
+    harness: &'a TestHarness,
+    fixtures: Vec<(String, Vec<u8>)>,
+}
+
+impl ControlClient<'_> {
+    /// Supply the encoded `value` under `name` to the tests, replacing the previous value.
+    pub fn set_fixture<T: Encode>(&mut self, name: impl Into<String>, value: &T) -> &mut Self {
+        let name = name.into();
+        let value = value.encode();
+
+        match self
+            .fixtures
+            .iter_mut()
+            .find(|(fixture_name, _)| *fixture_name == name)
+        {
+            Some((_, previous)) => *previous = value,
+            None => self.fixtures.push((name, value)),
+        }
+        self
+    }
+
+    /// Value supplied under `name`, `None` if there is no such value or it is not a `T`.
+    pub fn fixture<T: Decode>(&self, name: &str) -> Option<T> {
+        self.fixtures
+            .iter()
+            .find(|(fixture_name, _)| fixture_name == name)
+            .and_then(|(_, value)| T::decode(&mut &value[..]).ok())
+    }
+
+    /// Stop supplying the value under `name`, returns whether it was supplied.
+    pub fn remove_fixture(&mut self, name: &str) -> bool {
+        let len = self.fixtures.len();
+        self.fixtures
+            .retain(|(fixture_name, _)| fixture_name != name);
+        self.fixtures.len() != len
+    }
+
+    /// Run all tests and collect the report.
+    pub fn run(&self) -> anyhow::Result<Report> {
+        self.run_with(None)
+    }
+
+    /// Run only tests with the given full names (`module::test`) and collect the report.
+    pub fn run_filtered(&self, names: &[&str]) -> anyhow::Result<Report> {
+        let names = names.iter().map(|name| name.to_string()).collect();
+        self.run_with(Some(TestFilter::Names(names)))
+    }
+
+    /// Run only tests matching the filter and collect the report.
+    pub fn run_matching(&self, filter: TestFilter) -> anyhow::Result<Report> {
+        self.run_with(Some(filter))
+    }
+
+    fn run_with(&self, filter: Option<TestFilter>) -> anyhow::Result<Report> {
+        let harness = self.harness;
+        let system = System::new();
+        system.init_logger();
+
+        // test_program
+        let test_program = harness.deploy_test_program(&system)?;
+        system.mint_to(test_program.id(), harness.balance);
+
+        // code under test (code_hash)
+        let code_hash = system.submit_code(&harness.under_test_code);
+
+        // test program code, instantiated by tests as interposers
+        let interposer_code = system.submit_code(&harness.control_code);
+
+        // auxiliary codes
+        let codes = harness
+            .codes
+            .iter()
+            .map(|(name, path)| (name.clone(), system.submit_code(path).into_bytes().into()))
+            .collect();
+
+        // control bus program (for results telemetry)
+        let control_bus = control_bus::ControlBus::default().nocapture(harness.nocapture);
+        let running_state = control_bus.running_state();
+        let control = Program::mock(&system, control_bus);
+        // apparently it also should be initialized
+        let res = control.send_bytes(0, vec![]);
+        if res.main_failed() {
+            anyhow::bail!("Failed to initialize control bus");
+        }
+
+        // actual test run
+        let res = test_program.send(
+            0,
+            ControlSignal::Test {
+                code_hash: code_hash.into_bytes().into(),
+                control_bus: control.id().into_bytes().into(),
+                codes,
+                filter,
+                seed: harness.seed,
+                mode: harness.mode,
+                interposer_code: interposer_code.into_bytes().into(),
+                fixtures: self.fixtures.clone(),
+            },
+        );
+        if res.main_failed() {
+            anyhow::bail!("Test run failed to complete");
+        }
+
+        let report = running_state.read().unwrap().report(harness.seed);
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_code_is_rejected() {
+        let result = TestHarness::builder()
+            .control_code("does-not-exist_test.opt.wasm")
+            .under_test_code("does-not-exist.opt.wasm")
+            .build();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn runs_example_suite() {
+        let harness = TestHarness::builder()
+            .control_code("../target/wasm32-unknown-unknown/debug/example_test.opt.wasm")
+            .under_control_code("../target/wasm32-unknown-unknown/debug/example.opt.wasm")
+            .build()
+            .expect("Example should be built");
+
+        let report = harness.run().expect("Failed to run example suite");
+
+        // example contains twelve passing and two failing tests, a test and a property test
+        // expected to fail, three table-driven cases, and a benchmark
+        assert_eq!(report.total_started, 20);
+        assert_eq!(report.total_succeded, 17);
+        assert_eq!(report.total_failed, 2);
+        assert_eq!(report.total_skipped, 1);
+        assert!(report.suite_failures.is_empty());
+        assert!(!report.signals_lost());
+        assert_eq!(
+            report
+                .finished
+                .map(|summary| (summary.passed, summary.failed, summary.skipped)),
+            Some((18, 2, 1))
+        );
+        assert!(!report.success());
+        assert_eq!(report.seed, 0);
+        assert!(report.to_string().contains("--seed 0"));
+
+        // every started test (but the benchmark) reports gas, failed ones included
+        assert_eq!(
+            report.gas.len(),
+            report.total_started as usize - report.benches.len()
+        );
+        assert!(report.gas.iter().all(|(_, gas)| *gas > 0));
+
+        assert_eq!(report.benches.len(), 1);
+        assert!(report.benches[0].0.ends_with("::ping"));
+        assert!(report.benches[0].1 > 0);
+
+        let (_, bad_hint) = report
+            .failures
+            .iter()
+            .find(|(name, _)| name.ends_with("::bad"))
+            .expect("Bad test should fail");
+        assert!(bad_hint.contains("assertion"), "hint: {}", bad_hint);
+        assert!(bad_hint.contains("reply to PING"), "hint: {}", bad_hint);
+
+        // logged right before the failed assertion, so only carried by the failure
+        assert!(report
+            .logs
+            .iter()
+            .any(|(name, line)| name.ends_with("::bad") && line.starts_with("PING replied with")));
+
+        let (_, out_of_gas_hint) = report
+            .failures
+            .iter()
+            .find(|(name, _)| name.ends_with("::out_of_gas"))
+            .expect("Out of gas test should fail");
+        assert_eq!(out_of_gas_hint, "out of gas");
+    }
+
+    #[test]
+    fn lists_example_suite() {
+        let harness = TestHarness::builder()
+            .control_code("../target/wasm32-unknown-unknown/debug/example_test.opt.wasm")
+            .under_control_code("../target/wasm32-unknown-unknown/debug/example.opt.wasm")
+            .build()
+            .expect("Example should be built");
+
+        let tests = harness.list(None).expect("Failed to list example suite");
+
+        // everything but the hooks, matrix cases are listed separately
+        assert_eq!(tests.len(), 21);
+        assert!(tests
+            .iter()
+            .any(|test| test.name.ends_with("::ping") && test.bench));
+        assert!(tests
+            .iter()
+            .any(|test| test.name.ends_with("::pong") && test.ignored));
+        assert!(tests
+            .iter()
+            .any(|test| test.name.ends_with("::doubles::case_3")));
+
+        let tests = harness
+            .list(Some(TestFilter::Contains("::good".to_string())))
+            .expect("Failed to list example suite");
+        assert_eq!(tests.len(), 1);
+    }
+
+    #[test]
+    fn runs_filtered_example_suite() {
+        let harness = TestHarness::builder()
+            .control_code("../target/wasm32-unknown-unknown/debug/example_test.opt.wasm")
+            .under_control_code("../target/wasm32-unknown-unknown/debug/example.opt.wasm")
+            .build()
+            .expect("Example should be built");
+
+        let report = harness
+            .run_matching(TestFilter::Contains("::good".to_string()))
+            .expect("Failed to run example suite");
+
+        assert_eq!(report.total_started, 1);
+        assert_eq!(report.total_succeded, 1);
+        assert!(report.finished.is_some());
+        assert!(report.success());
+
+        let names = harness
+            .list(Some(TestFilter::Contains("::doubles".to_string())))
+            .expect("Failed to list example suite")
+            .into_iter()
+            .map(|test| test.name)
+            .collect::<Vec<_>>();
+        let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+
+        let report = harness
+            .run_filtered(&names[..2])
+            .expect("Failed to run example suite");
+
+        assert_eq!(report.total_started, 2);
+        assert_eq!(report.total_succeded, 2);
+        assert!(report.success());
+    }
+
+    #[test]
+    fn supplies_host_fixtures() {
+        let harness = TestHarness::builder()
+            .control_code("../target/wasm32-unknown-unknown/debug/example_test.opt.wasm")
+            .under_test_code("../target/wasm32-unknown-unknown/debug/example.opt.wasm")
+            .build()
+            .expect("Example should be built");
+        let filter = TestFilter::Contains("::reads_host_fixture".to_string());
+
+        let mut client = harness.control_client();
+        client.set_fixture("answer", &41u32);
+        assert_eq!(client.fixture::<u32>("answer"), Some(41));
+
+        let report = client
+            .run_matching(filter.clone())
+            .expect("Failed to run example suite");
+        assert_eq!(report.total_failed, 1);
+
+        client.set_fixture("answer", &42u32);
+        let report = client
+            .run_matching(filter)
+            .expect("Failed to run example suite");
+        assert_eq!(report.total_started, 1);
+        assert!(report.success(), "{}", report);
+
+        assert!(client.remove_fixture("answer"));
+        assert_eq!(client.fixture::<u32>("answer"), None);
+    }
+
+    #[test]
+    fn reports_wallet_balance() {
+        let harness = TestHarness::builder()
+            .control_code("../target/wasm32-unknown-unknown/debug/example_test.opt.wasm")
+            .under_control_code("../target/wasm32-unknown-unknown/debug/example.opt.wasm")
+            .build()
+            .expect("Example should be built");
+
+        // `balance_of` the wallet, not the test program
+        let report = harness
+            .run_matching(TestFilter::Contains(
+                "::wallet_keeps_transferred_value".to_string(),
+            ))
+            .expect("Failed to run example suite");
+
+        assert_eq!(report.total_started, 1);
+        assert!(report.success(), "{}", report);
+    }
+
+    #[test]
+    fn runs_example_suite_concurrently() {
+        let harness = TestHarness::builder()
+            .control_code("../target/wasm32-unknown-unknown/debug/example_test.opt.wasm")
+            .under_control_code("../target/wasm32-unknown-unknown/debug/example.opt.wasm")
+            .mode(RunMode::Concurrent(3))
+            .build()
+            .expect("Example should be built");
+
+        let report = harness
+            .run_matching(TestFilter::Contains("::doubles".to_string()))
+            .expect("Failed to run example suite");
+
+        assert_eq!(report.total_started, 3);
+        assert_eq!(report.total_succeded, 3);
+        assert!(report.success());
+    }
+}
@@ -0,0 +1,7 @@
+//! Test harness running the extracted test program in gtest, for use from the tests of the
+//! program itself (`cargo-gtest` is built on top of it).
+pub mod control_bus;
+pub mod harness;
+
+pub use control_bus::Report;
+pub use harness::{ControlClient, TestHarness, TestHarnessBuilder};
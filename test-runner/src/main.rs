@@ -1,14 +1,25 @@
 use anyhow::Context;
-use gear_test_runtime::ControlSignal;
-use gtest::{Program, System};
 use std::io::{prelude::*, BufReader};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use tempfile::NamedTempFile;
 
-mod control_bus;
+mod cli;
+mod gas;
 
-pub fn run_from_bin_path(bin_path_file: impl AsRef<Path>) -> anyhow::Result<()> {
+use cli::RunnerOptions;
+use gear_test_runner::{Report, TestHarness};
+
+pub fn run_from_bin_path(
+    bin_path_file: impl AsRef<Path>,
+    options: &RunnerOptions,
+) -> anyhow::Result<Report> {
+    let (wasm_bin_path, test_bin_path) = bin_paths(bin_path_file)?;
+
+    run_tests(wasm_bin_path, test_bin_path, options)
+}
+
+fn bin_paths(bin_path_file: impl AsRef<Path>) -> anyhow::Result<(PathBuf, PathBuf)> {
     let wasm_base = std::fs::read_to_string(bin_path_file.as_ref().to_path_buf())
         .with_context(|| format!("Reading {:?}", bin_path_file.as_ref().to_path_buf()))?;
 
@@ -19,71 +30,121 @@ pub fn run_from_bin_path(bin_path_file: impl AsRef<Path>) -> anyhow::Result<()>
 
     let test_bin_path = bin_base.join(PathBuf::from(format!("{wasm_base}_test.opt.wasm")));
 
-    run_tests(wasm_bin_path, test_bin_path)
+    Ok((wasm_bin_path, test_bin_path))
 }
 
-pub fn run_from_dir(directory: impl AsRef<Path>) -> anyhow::Result<()> {
+pub fn run_from_dir(
+    directory: impl AsRef<Path>,
+    options: &RunnerOptions,
+) -> anyhow::Result<Report> {
     let mut path = directory.as_ref().to_path_buf();
     path.push(".binpath");
 
-    run_from_bin_path(path)
+    run_from_bin_path(path, options)
+}
+
+fn harness(
+    program_wasm_path: impl AsRef<Path>,
+    progrm_test_path: impl AsRef<Path>,
+    options: &RunnerOptions,
+) -> anyhow::Result<TestHarness> {
+    let builder = TestHarness::builder()
+        .control_code(progrm_test_path)
+        .under_test_code(program_wasm_path)
+        .seed(options.seed.unwrap_or_else(random_seed))
+        .nocapture(options.nocapture)
+        .mode(options.run_mode());
+    let builder = match options.balance {
+        Some(balance) => builder.balance(balance),
+        None => builder,
+    };
+
+    options
+        .codes
+        .iter()
+        .fold(builder, |builder, (name, path)| builder.code(name, path))
+        .build()
+}
+
+/// Seed of the run when not given by `--seed` (printed on failure to reproduce the run).
+fn random_seed() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+
+    std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish()
+}
+
+/// Print tests (in the `cargo test -- --list` format) without running them.
+///
+/// Returns the number of listed tests.
+pub fn list_tests(
+    program_wasm_path: impl AsRef<Path>,
+    progrm_test_path: impl AsRef<Path>,
+    options: &RunnerOptions,
+) -> anyhow::Result<u32> {
+    let tests = harness(program_wasm_path, progrm_test_path, options)?
+        .list(options.test_filter())?;
+
+    for test in tests.iter() {
+        println!("{}: {}", test.name, if test.bench { "bench" } else { "test" });
+    }
+
+    let benches = tests.iter().filter(|test| test.bench).count();
+    println!("\n{} tests, {} benchmarks", tests.len() - benches, benches);
+
+    Ok(tests.len() as u32)
 }
 
 pub fn run_tests(
     program_wasm_path: impl AsRef<Path>,
     progrm_test_path: impl AsRef<Path>,
-) -> anyhow::Result<()> {
-    let system = System::new();
-    system.init_logger();
-
-    // test_program
-    let test_program = Program::from_file(&system, progrm_test_path);
-    let res = test_program.send_bytes(0, vec![]); // empty initialization for test program
-    assert!(!res.main_failed());
-
-    // code under test (code_hash)
-    let code_hash = system.submit_code(program_wasm_path);
-
-    // control bus program (for results telemetry)
-    let control_bus = control_bus::ControlBus::default();
-    let running_state = control_bus.running_state();
-    let control = Program::mock(&system, control_bus);
-    // apparently it also should be initialized
-    let res = control.send_bytes(0, vec![]);
-    assert!(!res.main_failed());
-
-    // actual test run
-    let res = test_program.send(
-        0,
-        ControlSignal::Test {
-            code_hash: code_hash.into_bytes().into(),
-            control_bus: control.id().into_bytes().into(),
-        },
-    );
-    assert!(!res.main_failed());
-
-    let report = running_state.read().unwrap().report();
+    options: &RunnerOptions,
+) -> anyhow::Result<Report> {
+    let harness = harness(program_wasm_path, progrm_test_path, options)?;
+    let report = match options.test_filter() {
+        Some(filter) => harness.run_matching(filter)?,
+        None => harness.run()?,
+    };
     println!("\n{}", report);
 
+    if options.print_gas_table() {
+        let baseline = match options.gas_baseline {
+            Some(ref path) => gas::parse_baseline(
+                &std::fs::read_to_string(path)
+                    .with_context(|| format!("Reading gas baseline {:?}", path))?,
+            ),
+            None => Vec::new(),
+        };
+        print!("{}", gas::table(&report.gas, &baseline));
+
+        let regressions = gas::regressions(&report.gas, &baseline);
+        if !regressions.is_empty() {
+            println!("gas regressions: {}", regressions.join(", "));
+        }
+    }
+
     if !report.success() {
         anyhow::bail!("Some test failed or unfinished!");
     }
 
-    Ok(())
+    Ok(report)
 }
 
-fn generate_cargo_args() -> Vec<String> {
+fn generate_cargo_args(args: &[String]) -> Vec<String> {
     ["build".to_string()]
         .into_iter()
-        .chain(std::env::args().skip(2))
+        .chain(args.iter().cloned())
         .collect()
 }
 
 fn main() -> anyhow::Result<()> {
+    let (options, args) = RunnerOptions::parse(std::env::args().skip(2))?;
+
     let builder_artifacts_file = NamedTempFile::new()?;
     let builder_artifacts_path = builder_artifacts_file.path().as_os_str();
 
-    let mut cargo_args = generate_cargo_args();
+    let mut cargo_args = generate_cargo_args(&args);
     cargo_args.push("--config".to_string());
     cargo_args.push(format!(
         "env.GEAR_BUILDER_ARTIFACTS=\"{}\"",
@@ -100,10 +161,11 @@ fn main() -> anyhow::Result<()> {
     if !build_out.status.success() {
         anyhow::bail!(
             "Cargo command failed (cargo {})",
-            generate_cargo_args().join(" ")
+            generate_cargo_args(&args).join(" ")
         );
     }
 
+    let mut total_started = 0;
     for line in BufReader::new(builder_artifacts_file).lines() {
         let line = line?;
         let clone_line = line.clone();
@@ -112,7 +174,15 @@ fn main() -> anyhow::Result<()> {
             anyhow::bail!("Got this from artifacts dump: '{}'. This is invalid, should be '<wasm_path>|<wasm_test_path>'", line);
         }
 
-        run_tests(paths[0], paths[1])?;
+        if options.list {
+            list_tests(paths[0], paths[1], &options)?;
+        } else {
+            total_started += run_tests(paths[0], paths[1], &options)?.total_started;
+        }
+    }
+
+    if !options.list {
+        options.check_test_count(total_started)?;
     }
 
     // file for gear_test_builder artifacts report;
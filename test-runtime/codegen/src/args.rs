@@ -0,0 +1,166 @@
+//! Arguments of the test attribute
+
+use syn::{
+    meta::ParseNestedMeta, punctuated::Punctuated, Expr, Ident, LitInt, LitStr, Result, Token,
+};
+
+const SUPPORTED_ARGS: &str =
+    "`should_fail`, `ignore`, `gas_limit`, `retries`, `tags`, `deploy`, `init`, `programs`";
+
+#[derive(Default)]
+pub struct TestArgs {
+    /// `should_fail` or `should_fail = "expected text"`
+    pub should_fail: Option<Option<LitStr>>,
+    /// `ignore` or `ignore = "reason"`
+    pub ignore: Option<Option<LitStr>>,
+    /// `gas_limit = 1_000_000`
+    pub gas_limit: Option<LitInt>,
+    /// `retries = 3`
+    pub retries: Option<LitInt>,
+    /// `tags("slow", "integration")`
+    pub tags: Option<Vec<LitStr>>,
+    /// `deploy = "token"` or `deploy("token", "dex")`
+    pub deploy: Option<Vec<LitStr>>,
+    /// `init = <expression>`, encoded init payload of the program under test
+    pub init: Option<Expr>,
+    /// `programs(token = "token", dex = "dex")`, fields of the struct and names of the codes
+    pub programs: Option<Vec<(Ident, LitStr)>>,
+}
+
+fn ensure_unique<T>(meta: &ParseNestedMeta, target: &Option<T>, name: &str) -> Result<()> {
+    match target {
+        Some(_) => Err(meta.error(format!("duplicate `{}` argument", name))),
+        None => Ok(()),
+    }
+}
+
+/// Parse `name` or `name = "text"` argument
+fn flag_with_text(
+    meta: &ParseNestedMeta,
+    target: &mut Option<Option<LitStr>>,
+    name: &str,
+) -> Result<()> {
+    ensure_unique(meta, target, name)?;
+    *target = if meta.input.peek(Token![=]) {
+        Some(Some(meta.value()?.parse()?))
+    } else {
+        Some(None)
+    };
+    Ok(())
+}
+
+/// Parse `name = <integer>` argument, checking it fits into `N`
+fn int_value<N>(meta: &ParseNestedMeta, target: &mut Option<LitInt>, name: &str) -> Result<()>
+where
+    N: core::str::FromStr,
+    N::Err: core::fmt::Display,
+{
+    ensure_unique(meta, target, name)?;
+    let value: LitInt = meta.value()?.parse()?;
+    value.base10_parse::<N>()?;
+    *target = Some(value);
+    Ok(())
+}
+
+/// Parse `name("a", "b", ...)` argument
+fn string_list(
+    meta: &ParseNestedMeta,
+    target: &mut Option<Vec<LitStr>>,
+    name: &str,
+) -> Result<()> {
+    ensure_unique(meta, target, name)?;
+    let content;
+    syn::parenthesized!(content in meta.input);
+    let list = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+    *target = Some(list.into_iter().collect());
+    Ok(())
+}
+
+/// Reject arguments creating programs before the test body, `entries` name the declared
+/// entries in the error
+fn reject_deploy(meta: &ParseNestedMeta, entries: &str) -> Result<()> {
+    if ["deploy", "init", "programs"]
+        .iter()
+        .any(|name| meta.path.is_ident(name))
+    {
+        Err(meta.error(format!(
+            "`deploy`, `init` and `programs` are not supported by {}",
+            entries
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Arguments of the property test attribute: `cases = N` along with the test arguments
+#[derive(Default)]
+pub struct PropTestArgs {
+    /// `cases = 64`
+    pub cases: Option<LitInt>,
+    pub test: TestArgs,
+}
+
+impl PropTestArgs {
+    pub fn parse(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        if meta.path.is_ident("cases") {
+            int_value::<u32>(&meta, &mut self.cases, "cases")
+        } else {
+            reject_deploy(&meta, "property tests")?;
+            self.test.parse(meta)
+        }
+    }
+}
+
+impl TestArgs {
+    /// Parse arguments of the `test` attribute declared along with `test_case` / `test_matrix!`,
+    /// shared by all the cases
+    pub fn parse_case(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        reject_deploy(&meta, "test cases")?;
+        self.parse(meta)
+    }
+
+    pub fn parse(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        if meta.path.is_ident("should_fail") {
+            flag_with_text(&meta, &mut self.should_fail, "should_fail")
+        } else if meta.path.is_ident("ignore") {
+            flag_with_text(&meta, &mut self.ignore, "ignore")
+        } else if meta.path.is_ident("gas_limit") {
+            int_value::<u64>(&meta, &mut self.gas_limit, "gas_limit")
+        } else if meta.path.is_ident("retries") {
+            int_value::<u32>(&meta, &mut self.retries, "retries")
+        } else if meta.path.is_ident("tags") {
+            string_list(&meta, &mut self.tags, "tags")
+        } else if meta.path.is_ident("deploy") {
+            if meta.input.peek(Token![=]) {
+                ensure_unique(&meta, &self.deploy, "deploy")?;
+                self.deploy = Some(vec![meta.value()?.parse()?]);
+                Ok(())
+            } else {
+                string_list(&meta, &mut self.deploy, "deploy")
+            }
+        } else if meta.path.is_ident("init") {
+            ensure_unique(&meta, &self.init, "init")?;
+            self.init = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("programs") {
+            ensure_unique(&meta, &self.programs, "programs")?;
+            let mut programs = Vec::new();
+            meta.parse_nested_meta(|program| {
+                let field = program
+                    .path
+                    .get_ident()
+                    .cloned()
+                    .ok_or_else(|| program.error("expected `field = \"code name\"`"))?;
+                programs.push((field, program.value()?.parse()?));
+                Ok(())
+            })?;
+            self.programs = Some(programs);
+            Ok(())
+        } else {
+            Err(meta.error(format!(
+                "unsupported test argument, expected one of {}",
+                SUPPORTED_ARGS
+            )))
+        }
+    }
+}
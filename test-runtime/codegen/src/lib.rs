@@ -1,31 +1,1009 @@
-
 //! Provides macros for async runtime of Gear programs.
 
 use proc_macro::TokenStream;
-use proc_macro2::{Ident, Span};
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
 use quote::quote;
+use syn::{punctuated::Punctuated, Expr, ItemFn, LitStr, Token};
 
-#[proc_macro_attribute]
-pub fn test(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    let function = syn::parse_macro_input!(item as syn::ItemFn);
-    let ident = &function.sig.ident;
-    let extern_ident = Ident::new(&format!("test_{}", ident), Span::call_site());
+mod args;
+
+use args::{PropTestArgs, TestArgs};
+
+/// Manifest data of the entry besides its export and name.
+struct EntryMeta {
+    /// Variant of `gear_test_runtime::TestKind`
+    kind: &'static str,
+    doc: String,
+    tags: Vec<LitStr>,
+}
+
+impl EntryMeta {
+    fn new(kind: &'static str, function: &ItemFn) -> Self {
+        let doc = function
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("doc"))
+            .filter_map(|attr| match &attr.meta {
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    value:
+                        Expr::Lit(syn::ExprLit {
+                            lit: syn::Lit::Str(line),
+                            ..
+                        }),
+                    ..
+                }) => Some(line.value().trim().to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        EntryMeta {
+            kind,
+            doc,
+            tags: Vec::new(),
+        }
+    }
+
+    fn tags(mut self, tags: Option<&Vec<LitStr>>) -> Self {
+        self.tags = tags.cloned().unwrap_or_default();
+        self
+    }
+}
+
+/// Record of the entry in the `gtest-manifest` custom section (see
+/// `gear_test_runtime::manifest`).
+fn manifest_record(extern_ident: &Ident, name: &TokenStream2, meta: &EntryMeta) -> TokenStream2 {
+    let export = extern_ident.to_string();
+    let kind = Ident::new(meta.kind, Span::call_site());
+    let doc = &meta.doc;
+    let tags = &meta.tags;
 
     quote! {
-        #function
+        const _: () = {
+            const NAME: &str = #name;
+            const TAGS: &[&str] = &[#(#tags),*];
+            const LEN: usize = gear_test_runtime::manifest::record_len(#export, NAME, #doc, TAGS);
 
+            #[cfg_attr(target_arch = "wasm32", link_section = "gtest-manifest")]
+            #[used]
+            static RECORD: [u8; LEN] = gear_test_runtime::manifest::record::<LEN>(
+                #export,
+                NAME,
+                gear_test_runtime::TestKind::#kind as u8,
+                #doc,
+                TAGS,
+            );
+        };
+    }
+}
+
+/// Exported test entry, registering the test future under the given name.
+///
+/// `body` is executed with `session` in scope.
+fn test_entry(
+    extern_ident: &Ident,
+    name: TokenStream2,
+    body: TokenStream2,
+    options: TokenStream2,
+    meta: EntryMeta,
+) -> TokenStream2 {
+    let record = manifest_record(extern_ident, &name, &meta);
+
+    quote! {
         #[no_mangle]
         pub unsafe extern "C" fn #extern_ident() {
             let test_future = gear_test_runtime::ContextFuture::new(
                 async {
-                    let session = gear_test_runtime::active_session();
-                    #ident(&session).await;
+                    let session: gear_test_runtime::Session = gear_test_runtime::active_session();
+                    #body
                 },
-                concat!(module_path!(), "::", stringify!(#ident)),
-            );
+                #name,
+            )
+            #options;
 
             gear_test_runtime::CONTEXT_FUTURES.push(test_future);
         }
+
+        #record
+    }
+}
+
+/// Checks that the declared function takes the session reference as the first argument.
+///
+/// Exact type is checked by the compiler at the call site of the generated entry.
+fn check_session_arg(function: &ItemFn) -> syn::Result<()> {
+    match function.sig.inputs.first() {
+        Some(syn::FnArg::Typed(arg)) if matches!(*arg.ty, syn::Type::Reference(_)) => Ok(()),
+        Some(arg) => Err(syn::Error::new_spanned(
+            arg,
+            "first argument should be the session: `&gear_test_runtime::Session`",
+        )),
+        None => Err(syn::Error::new_spanned(
+            &function.sig,
+            "test function should take the session as the first argument: `&gear_test_runtime::Session`",
+        )),
+    }
+}
+
+/// Name of the function constructing the (cached) value of the fixture.
+fn fixture_helper(ident: &Ident) -> Ident {
+    Ident::new(&format!("__gtest_fixture_{}", ident), ident.span())
+}
+
+/// Fixtures requested by the declared function: arguments following the first `skip` ones
+/// (the session and the arguments supplied otherwise), resolved by name.
+///
+/// Returns the argument names and the statements constructing them in the `session` scope.
+fn fixture_args(function: &ItemFn, skip: usize) -> syn::Result<(Vec<Ident>, TokenStream2)> {
+    let mut names = Vec::new();
+    let mut statements = TokenStream2::new();
+
+    for arg in function.sig.inputs.iter().skip(skip) {
+        let name = match arg {
+            syn::FnArg::Typed(syn::PatType { pat, .. }) => match &**pat {
+                syn::Pat::Ident(pat) => pat.ident.clone(),
+                pat => {
+                    return Err(syn::Error::new_spanned(
+                        pat,
+                        "fixture argument should be a plain identifier, named after the fixture",
+                    ));
+                }
+            },
+            syn::FnArg::Receiver(_) => {
+                return Err(syn::Error::new_spanned(arg, "test cannot take `self`"));
+            }
+        };
+
+        let value = Ident::new(&format!("__fixture_{}", name), Span::call_site());
+        let helper = fixture_helper(&name);
+        statements.extend(quote! { let #value = #helper(&session).await; });
+        names.push(value);
+    }
+
+    Ok((names, statements))
+}
+
+/// Struct of the programs declared by `programs(...)` argument, passed at `position`.
+///
+/// Returns the variable name and the statement constructing it in the `session` scope.
+fn programs_arg(
+    function: &ItemFn,
+    position: usize,
+    programs: Option<&[(Ident, LitStr)]>,
+) -> syn::Result<(Option<Ident>, TokenStream2)> {
+    let Some(programs) = programs else {
+        return Ok((None, TokenStream2::new()));
+    };
+
+    let ty = match function.sig.inputs.iter().nth(position) {
+        Some(syn::FnArg::Typed(arg)) => match &*arg.ty {
+            syn::Type::Path(ty) => ty,
+            ty => {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "programs should be passed as a struct, e.g. `programs: Programs`",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &function.sig,
+                "test declaring `programs(...)` should take the struct of them after the session",
+            ));
+        }
+    };
+
+    let (fields, codes): (Vec<_>, Vec<_>) = programs.iter().cloned().unzip();
+    let value = Ident::new("__programs", Span::call_site());
+    let statement = quote! {
+        let #value = #ty {
+            #(#fields: session.deploy(#codes).await,)*
+        };
+    };
+
+    Ok((Some(value), statement))
+}
+
+/// Awaits the call of the declared function, if it is async (synchronous tests are supported).
+fn maybe_await(function: &ItemFn) -> TokenStream2 {
+    match function.sig.asyncness {
+        Some(_) => quote! { .await },
+        None => TokenStream2::new(),
+    }
+}
+
+/// Builder calls of the `ContextFuture` for the declared test arguments (except `deploy`).
+fn test_options(args: &TestArgs) -> TokenStream2 {
+    let should_fail = args.should_fail.as_ref().map(|expected| match expected {
+        Some(expected) => quote! { .should_fail(Some(#expected)) },
+        None => quote! { .should_fail(None) },
+    });
+    let ignore = args.ignore.as_ref().map(|reason| match reason {
+        Some(reason) => quote! { .ignore(Some(#reason)) },
+        None => quote! { .ignore(None) },
+    });
+    let gas_limit = args
+        .gas_limit
+        .as_ref()
+        .map(|gas_limit| quote! { .gas_limit(#gas_limit) });
+    let retries = args
+        .retries
+        .as_ref()
+        .map(|retries| quote! { .retries(#retries) });
+    let tags = args.tags.as_ref().map(|tags| quote! { .tags(&[#(#tags),*]) });
+
+    quote! { #should_fail #ignore #gas_limit #retries #tags }
+}
+
+/// Declare test.
+///
+/// Test function can be either `async` or synchronous, and receives the session
+/// (`&gear_test_runtime::Session`) as the first argument.
+///
+/// Supported arguments:
+/// - `should_fail` - test passes only if it fails
+/// - `should_fail = "text"` - test passes only if it fails with the hint containing `text`
+/// - `ignore` or `ignore = "reason"` - test is listed, but reported as skipped instead of run
+/// - `gas_limit = N` - test fails if its body burns more than `N` gas
+/// - `retries = N` - failed test is executed again up to `N` times before reporting the failure
+/// - `tags("a", "b", ...)` - tags to select tests by (see `gear_test_runtime::TestFilter`)
+/// - `deploy = "name"` or `deploy("a", "b", ...)` - create programs from the auxiliary codes
+///   supplied in `ControlSignal::Test` under these names, their `ActorId`s are passed to the
+///   test after the session, in the same order
+/// - `init = expr` - create program from the code under test, initialized with the encoded
+///   value of `expr`, before the test body runs, its `ActorId` is `session.actor()`
+/// - `programs(field = "name", ...)` - create programs from the named auxiliary codes and pass
+///   them to the test as a struct (declared by the test argument type, following the deployed
+///   ones), with the given fields holding their `ActorId`s
+///
+/// Remaining arguments are fixtures (see `fixture`), resolved by the argument name.
+///
+/// ```ignore
+/// struct Programs {
+///     token: ActorId,
+///     dex: ActorId,
+/// }
+///
+/// #[gear_test_codegen::test(programs(token = "token", dex = "dex"))]
+/// async fn swap(session: &gear_test_runtime::Session, programs: Programs) {}
+/// ```
+#[proc_macro_attribute]
+pub fn test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut args = TestArgs::default();
+    let args_parser = syn::meta::parser(|meta| args.parse(meta));
+    syn::parse_macro_input!(attr with args_parser);
+
+    let function = syn::parse_macro_input!(item as ItemFn);
+    if let Err(e) = check_session_arg(&function) {
+        return e.into_compile_error().into();
+    }
+    let ident = &function.sig.ident;
+    let extern_ident = Ident::new(&format!("test_{}", ident), Span::call_site());
+
+    let options = test_options(&args);
+
+    let deployed = args
+        .deploy
+        .iter()
+        .flatten()
+        .enumerate()
+        .map(|(index, _)| Ident::new(&format!("__deployed_{}", index), Span::call_site()))
+        .collect::<Vec<_>>();
+    let deploy_names = args.deploy.iter().flatten();
+    let init = args.init.as_ref().map(|init| {
+        quote! {
+            let __actor = session.deploy_testee(#init).await;
+            let session = session.with_actor(__actor);
+        }
+    });
+
+    let programs_position = 1 + deployed.len();
+    let (programs, programs_statement) =
+        match programs_arg(&function, programs_position, args.programs.as_deref()) {
+            Ok(programs) => programs,
+            Err(e) => return e.into_compile_error().into(),
+        };
+
+    let fixtures_position = programs_position + usize::from(programs.is_some());
+    let (fixtures, fixture_statements) = match fixture_args(&function, fixtures_position) {
+        Ok(fixtures) => fixtures,
+        Err(e) => return e.into_compile_error().into(),
+    };
+
+    let call_await = maybe_await(&function);
+
+    let entry = test_entry(
+        &extern_ident,
+        quote! { concat!(module_path!(), "::", stringify!(#ident)) },
+        quote! {
+            #init
+            #(let #deployed = session.deploy(#deploy_names).await;)*
+            #programs_statement
+            #fixture_statements
+            #ident(&session, #(#deployed,)* #(#programs,)* #(#fixtures),*)#call_await;
+        },
+        options,
+        EntryMeta::new("Test", &function).tags(args.tags.as_ref()),
+    );
+
+    quote! {
+        #function
+
+        #entry
     }
     .into()
 }
+
+/// Declare gas benchmark.
+///
+/// Instead of success, reports gas burned by the function body (measured with
+/// `gstd::exec::gas_available`). Failure is reported as for regular test.
+#[proc_macro_attribute]
+pub fn bench(attr: TokenStream, item: TokenStream) -> TokenStream {
+    syn::parse_macro_input!(attr as syn::parse::Nothing);
+
+    let function = syn::parse_macro_input!(item as ItemFn);
+    if let Err(e) = check_session_arg(&function) {
+        return e.into_compile_error().into();
+    }
+    let ident = &function.sig.ident;
+    let extern_ident = Ident::new(&format!("test_{}", ident), Span::call_site());
+
+    let call_await = maybe_await(&function);
+
+    let entry = test_entry(
+        &extern_ident,
+        quote! { concat!(module_path!(), "::", stringify!(#ident)) },
+        quote! { #ident(&session)#call_await; },
+        quote! { .bench() },
+        EntryMeta::new("Bench", &function),
+    );
+
+    quote! {
+        #function
+
+        #entry
+    }
+    .into()
+}
+
+fn hook(
+    attr: TokenStream,
+    item: TokenStream,
+    kind: &str,
+    kind_variant: &'static str,
+) -> TokenStream {
+    syn::parse_macro_input!(attr as syn::parse::Nothing);
+
+    let function = syn::parse_macro_input!(item as ItemFn);
+    if let Err(e) = check_session_arg(&function) {
+        return e.into_compile_error().into();
+    }
+    let ident = &function.sig.ident;
+    let extern_ident = Ident::new(&format!("test_{}_{}", kind, ident), Span::call_site());
+    let kind = Ident::new(kind, Span::call_site());
+
+    let call_await = maybe_await(&function);
+
+    let entry = test_entry(
+        &extern_ident,
+        quote! { concat!(module_path!(), "::", stringify!(#ident)) },
+        quote! { #ident(&session)#call_await; },
+        quote! { .#kind() },
+        EntryMeta::new(kind_variant, &function),
+    );
+
+    quote! {
+        #function
+
+        #entry
+    }
+    .into()
+}
+
+/// Declare fixture: function constructing a value tests can request by taking an argument
+/// named after the fixture.
+///
+/// The value is constructed once per session (on the first request) and cloned into every
+/// test requesting it, so it should be `Clone + 'static`. Fixture receives the session as the
+/// first argument and can request other fixtures the same way tests do. Fixtures are resolved
+/// in the scope of the requesting function, so fixtures from other modules should be imported
+/// along with their `__gtest_fixture_*` helpers (e.g. with `use fixtures::*`).
+///
+/// ```ignore
+/// #[gear_test_codegen::fixture]
+/// async fn pinger(session: &gear_test_runtime::Session) -> ActorId {
+///     deploy_pinger(session.testee()).await
+/// }
+///
+/// #[gear_test_codegen::test]
+/// async fn ping(session: &gear_test_runtime::Session, pinger: ActorId) {}
+/// ```
+#[proc_macro_attribute]
+pub fn fixture(attr: TokenStream, item: TokenStream) -> TokenStream {
+    syn::parse_macro_input!(attr as syn::parse::Nothing);
+
+    let function = syn::parse_macro_input!(item as ItemFn);
+
+    match fixture_impl(&function) {
+        Ok(helper) => quote! {
+            #function
+
+            #helper
+        }
+        .into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+fn fixture_impl(function: &ItemFn) -> syn::Result<TokenStream2> {
+    check_session_arg(function)?;
+
+    let ident = &function.sig.ident;
+    let vis = &function.vis;
+    let helper = fixture_helper(ident);
+    let call_await = maybe_await(function);
+    let ty = match &function.sig.output {
+        syn::ReturnType::Type(_, ty) => ty,
+        syn::ReturnType::Default => {
+            return Err(syn::Error::new_spanned(
+                &function.sig,
+                "fixture should return the value it constructs",
+            ));
+        }
+    };
+    let (fixtures, fixture_statements) = fixture_args(function, 1)?;
+
+    Ok(quote! {
+        #[doc(hidden)]
+        #vis async fn #helper(session: &gear_test_runtime::Session) -> #ty {
+            const NAME: &str = concat!(module_path!(), "::", stringify!(#ident));
+
+            if let Some(value) = gear_test_runtime::fixtures::get::<#ty>(NAME) {
+                return value;
+            }
+
+            #fixture_statements
+            let value = #ident(session, #(#fixtures),*)#call_await;
+            gear_test_runtime::fixtures::put(NAME, Clone::clone(&value));
+            value
+        }
+    })
+}
+
+/// Declare hook executed before every test (in the same message as the test).
+#[proc_macro_attribute]
+pub fn setup(attr: TokenStream, item: TokenStream) -> TokenStream {
+    hook(attr, item, "setup", "Setup")
+}
+
+/// Declare hook executed after every test (in the same message as the test).
+///
+/// If the test fails, the hook is executed in a separate message after it.
+#[proc_macro_attribute]
+pub fn teardown(attr: TokenStream, item: TokenStream) -> TokenStream {
+    hook(attr, item, "teardown", "Teardown")
+}
+
+/// Declare hook executed once before the first test (in a separate message).
+///
+/// Memory changes of the hook are kept, so it can prepare state shared by the tests (e.g.
+/// cache a fixture). If the hook fails, the tests are reported as skipped.
+#[proc_macro_attribute]
+pub fn before_all(attr: TokenStream, item: TokenStream) -> TokenStream {
+    hook(attr, item, "before_all", "BeforeAll")
+}
+
+/// Declare hook executed once after the last test (in a separate message).
+///
+/// Executed even if `before_all` hook fails.
+#[proc_macro_attribute]
+pub fn after_all(attr: TokenStream, item: TokenStream) -> TokenStream {
+    hook(attr, item, "after_all", "AfterAll")
+}
+
+/// Declare property test.
+///
+/// Arguments following the session are generated (see `gear_test_runtime::proptest::Arbitrary`)
+/// from the seed of the test run, and the body is executed for each of `cases` generated inputs
+/// (`gear_test_runtime::proptest::DEFAULT_CASES` if not specified). If any case fails, the input
+/// is shrunk and the test fails with the minimal counterexample found.
+///
+/// Supports the same arguments as `test` (except `deploy`) along with `cases = N`.
+///
+/// ```ignore
+/// #[gear_test_codegen::proptest(cases = 64)]
+/// fn addition_commutes(_session: &gear_test_runtime::Session, a: u32, b: u32) {
+///     assert_eq!(a.wrapping_add(b), b.wrapping_add(a));
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn proptest(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut args = PropTestArgs::default();
+    let args_parser = syn::meta::parser(|meta| args.parse(meta));
+    syn::parse_macro_input!(attr with args_parser);
+
+    let function = syn::parse_macro_input!(item as ItemFn);
+
+    match proptest_impl(&args, &function) {
+        Ok(entry) => quote! {
+            #function
+
+            #entry
+        }
+        .into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+fn proptest_impl(args: &PropTestArgs, function: &ItemFn) -> syn::Result<TokenStream2> {
+    check_session_arg(function)?;
+
+    let ident = &function.sig.ident;
+    let extern_ident = Ident::new(&format!("test_{}", ident), Span::call_site());
+    let call_await = maybe_await(function);
+
+    let mut input_types = Vec::new();
+    for arg in function.sig.inputs.iter().skip(1) {
+        match arg {
+            syn::FnArg::Typed(arg) => input_types.push(&arg.ty),
+            syn::FnArg::Receiver(_) => {
+                return Err(syn::Error::new_spanned(arg, "property test cannot take `self`"));
+            }
+        }
+    }
+    if input_types.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &function.sig,
+            "property test should take generated arguments after the session",
+        ));
+    }
+    let inputs = (0..input_types.len())
+        .map(|index| Ident::new(&format!("__input_{}", index), Span::call_site()))
+        .collect::<Vec<_>>();
+
+    let cases = match &args.cases {
+        Some(cases) => quote! { #cases },
+        None => quote! { gear_test_runtime::proptest::DEFAULT_CASES },
+    };
+
+    Ok(test_entry(
+        &extern_ident,
+        quote! { concat!(module_path!(), "::", stringify!(#ident)) },
+        quote! {
+            match gear_test_runtime::proptest::case_input::<(#(#input_types,)*)>() {
+                Some((#(#inputs,)*)) => {
+                    #ident(&session, #(#inputs),*)#call_await;
+                }
+                None => {
+                    gear_test_runtime::proptest::check::<(#(#input_types,)*)>(#cases).await;
+                }
+            }
+        },
+        test_options(&args.test),
+        EntryMeta::new("Test", function).tags(args.test.tags.as_ref()),
+    ))
+}
+
+/// Input of `test_matrix!`: literal array of the cases followed by the test function.
+struct MatrixInput {
+    cases: syn::ExprArray,
+    function: ItemFn,
+}
+
+impl syn::parse::Parse for MatrixInput {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let cases = input.parse()?;
+        input.parse::<Token![;]>()?;
+        let function = input.parse()?;
+
+        Ok(MatrixInput { cases, function })
+    }
+}
+
+/// Declare table-driven test.
+///
+/// Takes a literal array of `(input, expected)` pairs followed by the test function, which
+/// receives the session, input and expected value. Each pair produces separate test named
+/// `<test>::case_<n>` (numbered from 1 in order of the array), with its own export and
+/// manifest record, same as `test_case`.
+///
+/// Arguments of the `test` attribute on the function apply to every case, same as for
+/// `test_case`.
+///
+/// ```ignore
+/// gear_test_codegen::test_matrix! {
+///     [(1, 2), (2, 4), (3, 6)];
+///     #[test(tags("arithmetic"))]
+///     fn doubles(_session: &gear_test_runtime::Session, input: u32, expected: u32) {
+///         assert_eq!(input * 2, expected);
+///     }
+/// }
+/// ```
+#[proc_macro]
+pub fn test_matrix(item: TokenStream) -> TokenStream {
+    let mut input = syn::parse_macro_input!(item as MatrixInput);
+
+    match test_matrix_impl(&mut input) {
+        Ok(entries) => {
+            let function = &input.function;
+            quote! {
+                #function
+
+                #entries
+            }
+            .into()
+        }
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+fn test_matrix_impl(input: &mut MatrixInput) -> syn::Result<TokenStream2> {
+    let MatrixInput { cases, function } = input;
+    let args = case_args(function)?;
+    check_session_arg(function)?;
+
+    if function.sig.inputs.len() != 3 {
+        return Err(syn::Error::new_spanned(
+            &function.sig.inputs,
+            "table-driven test should take the session, input and expected value",
+        ));
+    }
+
+    let cases = cases
+        .elems
+        .iter()
+        .map(|case| match case {
+            Expr::Tuple(tuple) if tuple.elems.len() == 2 => Ok(tuple.elems.clone()),
+            case => Err(syn::Error::new_spanned(
+                case,
+                "case should be a pair of the input and expected value: `(input, expected)`",
+            )),
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    case_entries(function, cases, &args)
+}
+
+type CaseArgs = Punctuated<Expr, Token![,]>;
+
+/// Whether the attribute is `name` (possibly by the path, e.g. `gear_test_codegen::name`).
+fn is_attr(attr: &syn::Attribute, name: &str) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .map(|segment| segment.ident == name)
+        .unwrap_or(false)
+}
+
+/// Removes `test` attributes from the function, parsing their arguments shared by the cases.
+fn case_args(function: &mut ItemFn) -> syn::Result<TestArgs> {
+    let (test_attrs, other_attrs): (Vec<_>, Vec<_>) = function
+        .attrs
+        .drain(..)
+        .partition(|attr| is_attr(attr, "test"));
+    function.attrs = other_attrs;
+
+    let mut args = TestArgs::default();
+    for attr in test_attrs {
+        if let syn::Meta::List(_) = attr.meta {
+            attr.parse_nested_meta(|meta| args.parse_case(meta))?;
+        }
+    }
+
+    Ok(args)
+}
+
+/// Declare parameterized test.
+///
+/// Each `test_case(...)` attribute produces separate test named `<test>::case_<n>`
+/// (numbered from 1 in order of declaration), which calls the function with the session
+/// followed by the case arguments.
+///
+/// Arguments of the `test` attribute declared below the cases (except `deploy`, `init` and
+/// `programs`) apply to every case, the attribute itself is removed.
+///
+/// ```ignore
+/// #[gear_test_codegen::test_case(1, 2)]
+/// #[gear_test_codegen::test_case(2, 4)]
+/// #[gear_test_codegen::test(tags("arithmetic"), gas_limit = 1_000_000)]
+/// async fn doubles(session: &gear_test_runtime::Session, input: u32, expected: u32) {
+///     assert_eq!(input * 2, expected);
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn test_case(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let first_case = syn::parse_macro_input!(attr with CaseArgs::parse_terminated);
+    let mut function = syn::parse_macro_input!(item as ItemFn);
+
+    match test_case_impl(first_case, &mut function) {
+        Ok(entries) => quote! {
+            #function
+
+            #entries
+        }
+        .into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+fn test_case_impl(first_case: CaseArgs, function: &mut ItemFn) -> syn::Result<TokenStream2> {
+    let mut cases = vec![first_case];
+
+    // remaining `test_case` attributes are expanded here, not by the compiler
+    let (case_attrs, other_attrs): (Vec<_>, Vec<_>) = function
+        .attrs
+        .drain(..)
+        .partition(|attr| is_attr(attr, "test_case"));
+    function.attrs = other_attrs;
+    for attr in case_attrs {
+        cases.push(attr.parse_args_with(CaseArgs::parse_terminated)?);
+    }
+    let args = case_args(function)?;
+
+    check_session_arg(function)?;
+
+    case_entries(function, cases, &args)
+}
+
+/// Entries of the cases (`test_case` / `test_matrix!`), each calling the function with the
+/// session followed by the case arguments.
+fn case_entries(
+    function: &ItemFn,
+    cases: Vec<CaseArgs>,
+    args: &TestArgs,
+) -> syn::Result<TokenStream2> {
+    let ident = &function.sig.ident;
+    let call_await = maybe_await(function);
+    let case_arity = function.sig.inputs.len().saturating_sub(1);
+
+    let mut entries = TokenStream2::new();
+    for (number, case) in (1..).zip(cases) {
+        if case.len() != case_arity {
+            return Err(syn::Error::new_spanned(
+                &case,
+                format!(
+                    "test case has {} argument(s), but `{}` expects {} after the session",
+                    case.len(),
+                    ident,
+                    case_arity
+                ),
+            ));
+        }
+
+        let case_args = case.iter();
+        let extern_ident = Ident::new(
+            &format!("test_{}_case_{}", ident, number),
+            Span::call_site(),
+        );
+        let case_name = format!("case_{}", number);
+
+        entries.extend(test_entry(
+            &extern_ident,
+            quote! { concat!(module_path!(), "::", stringify!(#ident), "::", #case_name) },
+            quote! { #ident(&session, #(#case_args),*)#call_await; },
+            test_options(args),
+            EntryMeta::new("Test", function).tags(args.tags.as_ref()),
+        ));
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_expands_each_case() {
+        let mut function: ItemFn = syn::parse_quote! {
+            #[gear_test_codegen::test_case(2, 4)]
+            #[test_case(3, 6)]
+            async fn doubles(session: &Session, input: u32, expected: u32) {}
+        };
+
+        let entries = test_case_impl(syn::parse_quote!(1, 2), &mut function)
+            .expect("Failed to expand")
+            .to_string();
+
+        assert!(function.attrs.is_empty());
+        for number in 1..=3 {
+            assert!(entries.contains(&format!("fn test_doubles_case_{} ()", number)));
+            assert!(entries.contains(&format!("\"case_{}\"", number)));
+        }
+        assert!(!entries.contains("test_doubles_case_4"));
+    }
+
+    #[test]
+    fn session_argument_is_required() {
+        let mut function: ItemFn = syn::parse_quote! {
+            async fn doubles(input: u32) {}
+        };
+
+        assert!(test_case_impl(syn::parse_quote!(), &mut function).is_err());
+    }
+
+    #[test]
+    fn proptest_decodes_case_input() {
+        let function: ItemFn = syn::parse_quote! {
+            fn sum_commutes(session: &Session, a: u32, b: Vec<u8>) {}
+        };
+
+        let entry = proptest_impl(&PropTestArgs::default(), &function)
+            .expect("Failed to expand")
+            .to_string();
+
+        assert!(entry.contains("fn test_sum_commutes ()"));
+        assert!(entry.contains("case_input"));
+        assert!(entry.contains("u32 , Vec < u8 >"));
+        assert!(entry.contains("DEFAULT_CASES"));
+    }
+
+    #[test]
+    fn proptest_requires_inputs() {
+        let function: ItemFn = syn::parse_quote! {
+            fn nothing_to_generate(session: &Session) {}
+        };
+
+        assert!(proptest_impl(&PropTestArgs::default(), &function).is_err());
+    }
+
+    #[test]
+    fn fixture_is_cached() {
+        let function: ItemFn = syn::parse_quote! {
+            async fn pinger(session: &Session, deployer: ActorId) -> ActorId {}
+        };
+
+        let helper = fixture_impl(&function)
+            .expect("Failed to expand")
+            .to_string();
+
+        assert!(helper.contains("fn __gtest_fixture_pinger"));
+        assert!(helper.contains("let __fixture_deployer = __gtest_fixture_deployer"));
+        assert!(helper.contains("fixtures :: get"));
+        assert!(helper.contains("fixtures :: put"));
+    }
+
+    #[test]
+    fn fixture_returns_value() {
+        let function: ItemFn = syn::parse_quote! {
+            async fn nothing(session: &Session) {}
+        };
+
+        assert!(fixture_impl(&function).is_err());
+    }
+
+    #[test]
+    fn test_matrix_registers_every_case() {
+        let mut input: MatrixInput = syn::parse_quote! {
+            [(1, 2), (2, 4), (21, 42)];
+            async fn doubles(session: &Session, input: u32, expected: u32) {}
+        };
+
+        let entries = test_matrix_impl(&mut input)
+            .expect("Failed to expand")
+            .to_string();
+
+        for number in 1..=3 {
+            assert!(entries.contains(&format!("fn test_doubles_case_{} ()", number)));
+            assert!(entries.contains(&format!("\"case_{}\"", number)));
+        }
+        assert_eq!(entries.matches("link_section").count(), 3);
+        assert!(entries.contains("doubles (& session , 21 , 42)"));
+    }
+
+    #[test]
+    fn test_matrix_checks_arity() {
+        let mut input: MatrixInput = syn::parse_quote! {
+            [(1, 2)];
+            fn doubles(session: &Session, input: u32) {}
+        };
+        assert!(test_matrix_impl(&mut input).is_err());
+
+        let mut input: MatrixInput = syn::parse_quote! {
+            [(1, 2), 3];
+            fn doubles(session: &Session, input: u32, expected: u32) {}
+        };
+        assert!(test_matrix_impl(&mut input).is_err());
+    }
+
+    #[test]
+    fn test_matrix_passes_test_args() {
+        let mut input: MatrixInput = syn::parse_quote! {
+            [(1, 2), (2, 4)];
+            #[test(tags("slow"), ignore = "flaky")]
+            fn doubles(session: &Session, input: u32, expected: u32) {}
+        };
+
+        let entries = test_matrix_impl(&mut input)
+            .expect("Failed to expand")
+            .to_string();
+
+        assert!(input.function.attrs.is_empty());
+        assert_eq!(
+            entries.matches("TAGS : & [& str] = & [\"slow\"]").count(),
+            2
+        );
+        assert_eq!(entries.matches(". ignore (Some (\"flaky\"))").count(), 2);
+    }
+
+    #[test]
+    fn manifest_record_contains_doc_and_tags() {
+        let function: ItemFn = syn::parse_quote! {
+            /// Checks transfers.
+            async fn transfer(session: &Session) {}
+        };
+        let meta = EntryMeta::new("Test", &function).tags(Some(&vec![syn::parse_quote!("slow")]));
+
+        let record = manifest_record(
+            &Ident::new("test_transfer", Span::call_site()),
+            &quote! { "module::transfer" },
+            &meta,
+        )
+        .to_string();
+
+        assert!(record.contains("\"test_transfer\""));
+        assert!(record.contains("\"Checks transfers.\""));
+        assert!(record.contains("\"slow\""));
+        assert!(record.contains("TestKind :: Test as u8"));
+    }
+
+    #[test]
+    fn programs_are_deployed_into_struct() {
+        let function: ItemFn = syn::parse_quote! {
+            async fn swap(session: &Session, programs: Programs, pinger: ActorId) {}
+        };
+        let programs: Vec<(Ident, LitStr)> = vec![
+            (syn::parse_quote!(token), syn::parse_quote!("token")),
+            (syn::parse_quote!(dex), syn::parse_quote!("dex")),
+        ];
+
+        let (value, statement) =
+            programs_arg(&function, 1, Some(&programs[..])).expect("Failed to expand");
+
+        assert_eq!(value.expect("Programs should be passed").to_string(), "__programs");
+        let statement = statement.to_string();
+        assert!(statement.contains("Programs {"));
+        assert!(statement.contains("token : session . deploy (\"token\")"));
+        assert!(statement.contains("dex : session . deploy (\"dex\")"));
+
+        assert!(programs_arg(&function, 3, Some(&programs[..])).is_err());
+        assert!(programs_arg(&function, 1, None).expect("Failed to expand").0.is_none());
+    }
+
+    #[test]
+    fn test_case_checks_arity() {
+        let mut function: ItemFn = syn::parse_quote! {
+            async fn doubles(session: &Session, input: u32, expected: u32) {}
+        };
+
+        assert!(test_case_impl(syn::parse_quote!(1), &mut function).is_err());
+    }
+
+    #[test]
+    fn test_case_passes_test_args() {
+        let mut function: ItemFn = syn::parse_quote! {
+            #[test_case(2, 4)]
+            #[gear_test_codegen::test(tags("slow"), should_fail, retries = 2)]
+            async fn doubles(session: &Session, input: u32, expected: u32) {}
+        };
+
+        let entries = test_case_impl(syn::parse_quote!(1, 2), &mut function)
+            .expect("Failed to expand")
+            .to_string();
+
+        assert!(function.attrs.is_empty());
+        assert_eq!(
+            entries.matches("TAGS : & [& str] = & [\"slow\"]").count(),
+            2
+        );
+        assert_eq!(entries.matches(". should_fail (None)").count(), 2);
+        assert_eq!(entries.matches(". retries (2)").count(), 2);
+
+        let mut function: ItemFn = syn::parse_quote! {
+            #[test(deploy = "token")]
+            async fn doubles(session: &Session, input: u32, expected: u32) {}
+        };
+        assert!(test_case_impl(syn::parse_quote!(1, 2), &mut function).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! Structured assertions (`gear_test_runtime::assert_eq!` and friends).
+//!
+//! Failed assertion panics with the encoded `Assertion` as the panic message, the test loop
+//! decodes it out of the error reply and reports `TestUpdate::AssertionFailed` instead of
+//! the plain failure hint. Lines logged by the test are encoded along with it, since memory of
+//! the failed message is reverted.
+
+use codec::{Decode, Encode};
+use core::fmt;
+use gstd::prelude::*;
+
+use crate::includes;
+
+/// Prefix of the encoded assertion in the panic message.
+const MARKER: &str = "gtest-assertion:";
+
+/// Failed assertion, as reported to the control bus.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct Assertion {
+    pub file: String,
+    pub line: u32,
+    /// Asserted expression, as written in the test
+    pub expression: String,
+    /// Custom message of the assertion (can be empty)
+    pub message: String,
+    /// `Debug` representation of the expected value (right operand of `assert_eq!`)
+    pub expected: String,
+    /// `Debug` representation of the actual value (left operand of `assert_eq!`)
+    pub actual: String,
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "assertion `{}` failed at {}:{}",
+            self.expression, self.file, self.line
+        )?;
+        if !self.message.is_empty() {
+            write!(f, ": {}", self.message)?;
+        }
+        write!(
+            f,
+            "\n  expected: {}\n    actual: {}",
+            self.expected, self.actual
+        )
+    }
+}
+
+/// Terminate the test with the failed assertion.
+pub fn fail(assertion: Assertion) -> ! {
+    let encoded: String = (assertion, includes::current_logs())
+        .encode()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    panic!("{}{}", MARKER, encoded)
+}
+
+/// Assertion encoded in the panic message by `fail` along with the lines logged by the test,
+/// if any.
+pub(crate) fn extract(panic_message: &str) -> Option<(Assertion, Vec<String>)> {
+    let (_, tail) = panic_message.split_once(MARKER)?;
+    let hex = tail
+        .split(|c: char| !c.is_ascii_hexdigit())
+        .next()
+        .unwrap_or_default();
+
+    let bytes = (0..hex.len() / 2)
+        .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16))
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+
+    <(Assertion, Vec<String>)>::decode(&mut &bytes[..]).ok()
+}
+
+#[doc(hidden)]
+pub fn debug<T: fmt::Debug + ?Sized>(value: &T) -> String {
+    format!("{:?}", value)
+}
+
+#[doc(hidden)]
+pub fn message(args: fmt::Arguments) -> String {
+    format!("{}", args)
+}
+
+/// Assert that the condition holds, reporting the expression if it does not.
+#[macro_export]
+macro_rules! assert {
+    ($cond:expr $(,)?) => {
+        $crate::assert!($cond, "")
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            $crate::assertions::fail($crate::assertions::Assertion {
+                file: ::core::file!().into(),
+                line: ::core::line!(),
+                expression: ::core::stringify!($cond).into(),
+                message: $crate::assertions::message(::core::format_args!($($arg)+)),
+                expected: "true".into(),
+                actual: "false".into(),
+            })
+        }
+    };
+}
+
+/// Assert that the actual (left) value equals the expected (right) one, reporting both
+/// if it does not.
+#[macro_export]
+macro_rules! assert_eq {
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::assert_eq!($actual, $expected, "")
+    };
+    ($actual:expr, $expected:expr, $($arg:tt)+) => {
+        match (&$actual, &$expected) {
+            (actual, expected) => {
+                if !(*actual == *expected) {
+                    $crate::assertions::fail($crate::assertions::Assertion {
+                        file: ::core::file!().into(),
+                        line: ::core::line!(),
+                        expression: ::core::concat!(
+                            ::core::stringify!($actual),
+                            " == ",
+                            ::core::stringify!($expected)
+                        )
+                        .into(),
+                        message: $crate::assertions::message(::core::format_args!($($arg)+)),
+                        expected: $crate::assertions::debug(expected),
+                        actual: $crate::assertions::debug(actual),
+                    })
+                }
+            }
+        }
+    };
+}
+
+/// Assert that the actual (left) value differs from the unexpected (right) one.
+#[macro_export]
+macro_rules! assert_ne {
+    ($actual:expr, $unexpected:expr $(,)?) => {
+        $crate::assert_ne!($actual, $unexpected, "")
+    };
+    ($actual:expr, $unexpected:expr, $($arg:tt)+) => {
+        match (&$actual, &$unexpected) {
+            (actual, unexpected) => {
+                if *actual == *unexpected {
+                    $crate::assertions::fail($crate::assertions::Assertion {
+                        file: ::core::file!().into(),
+                        line: ::core::line!(),
+                        expression: ::core::concat!(
+                            ::core::stringify!($actual),
+                            " != ",
+                            ::core::stringify!($unexpected)
+                        )
+                        .into(),
+                        message: $crate::assertions::message(::core::format_args!($($arg)+)),
+                        expected: ::core::concat!("not ", ::core::stringify!($unexpected)).into(),
+                        actual: $crate::assertions::debug(actual),
+                    })
+                }
+            }
+        }
+    };
+}
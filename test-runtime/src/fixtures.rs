@@ -0,0 +1,41 @@
+//! Values of `#[gear_test_codegen::fixture]` functions, cached per session.
+//!
+//! Cache lives in the program memory, so the value is shared by the tests executed after the
+//! one which constructed it. Memory changes of the failed test are reverted, so fixture
+//! constructed by the failed test is constructed once again by the next one.
+
+#![allow(static_mut_refs)]
+
+use core::any::Any;
+use gstd::{prelude::*, MessageId};
+
+use crate::includes;
+
+static mut FIXTURES: Vec<(MessageId, &'static str, Box<dyn Any>)> = Vec::new();
+
+/// Cached value of the fixture in the current session.
+pub fn get<T: Clone + 'static>(name: &'static str) -> Option<T> {
+    let (session_id, _) = includes::current_test();
+
+    unsafe { FIXTURES.iter() }
+        .find(|(fixture_session, fixture_name, _)| {
+            fixture_session == &session_id && *fixture_name == name
+        })
+        .and_then(|(_, _, value)| value.downcast_ref::<T>())
+        .cloned()
+}
+
+/// Cache value of the fixture for the rest of the current session.
+pub fn put<T: 'static>(name: &'static str, value: T) {
+    let (session_id, _) = includes::current_test();
+
+    unsafe {
+        FIXTURES.push((session_id, name, Box::new(value)));
+    }
+}
+
+pub(crate) fn drop_session(session_id: &MessageId) {
+    unsafe {
+        FIXTURES.retain(|(fixture_session, _, _)| fixture_session != session_id);
+    }
+}
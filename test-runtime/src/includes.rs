@@ -1,10 +1,21 @@
 #![allow(static_mut_refs)]
 
-use super::ControlSignal;
-use core::{future::Future, pin::Pin};
-use gstd::{msg, prelude::*};
+use super::{ControlSignal, RunMode, TestDescription, TestFilter};
+use alloc::borrow::Cow;
+use codec::Decode;
+use core::{fmt, future::Future, pin::Pin};
+use futures::stream::{self, StreamExt};
+use gstd::{
+    errors::{Error, ErrorReplyReason, SimpleExecutionError},
+    exec, msg,
+    prelude::*,
+    MessageId,
+};
 
-use crate::sessions;
+use crate::{
+    assertions::{self, Assertion},
+    mocks, proptest, sessions,
+};
 
 #[derive(Debug)]
 pub enum TestResult {
@@ -31,120 +42,766 @@ unsafe fn read_tests(mut ptr: *const u8) -> Vec<unsafe extern "C" fn()> {
     result
 }
 
+/// What the registered entry is.
+///
+/// Discriminants are stored in the test manifest (see `manifest`), do not reorder.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TestKind {
+    /// Regular test, reported as success or failure
+    #[default]
+    Test = 0,
+    /// Benchmark, reports gas burned by its body instead of success
+    Bench = 1,
+    /// Hook executed before every test
+    Setup = 2,
+    /// Hook executed after every test
+    Teardown = 3,
+    /// Hook executed once before the first test
+    BeforeAll = 4,
+    /// Hook executed once after the last test
+    AfterAll = 5,
+}
+
+impl TestKind {
+    pub fn is_hook(&self) -> bool {
+        matches!(
+            self,
+            TestKind::Setup | TestKind::Teardown | TestKind::BeforeAll | TestKind::AfterAll
+        )
+    }
+
+    pub fn is_suite_hook(&self) -> bool {
+        matches!(self, TestKind::BeforeAll | TestKind::AfterAll)
+    }
+}
+
+/// Options of the test, as declared in the test attribute.
+#[derive(Debug, Clone, Default)]
+pub struct TestOptions {
+    pub kind: TestKind,
+    /// Test passes only if it fails, optionally with a hint containing the given text
+    pub should_fail: Option<Option<&'static str>>,
+    /// Test is not executed, but reported as skipped, optionally with the reason
+    pub ignore: Option<Option<&'static str>>,
+    /// Test fails if its body burns more gas than this
+    pub gas_limit: Option<u64>,
+    /// How many times failed test is executed again before reporting the failure
+    pub retries: u32,
+    pub tags: &'static [&'static str],
+}
+
 pub struct ContextFuture {
     fut: Pin<Box<dyn Future<Output = ()> + 'static>>,
-    name: &'static str,
+    name: Cow<'static, str>,
+    options: TestOptions,
 }
 
 impl ContextFuture {
     pub fn new(
         fut: impl future::Future<Output = ()> + 'static + gstd::Send,
-        name: &'static str,
+        name: impl Into<Cow<'static, str>>,
     ) -> Self {
         use futures::FutureExt;
         ContextFuture {
             fut: fut.boxed(),
-            name,
+            name: name.into(),
+            options: TestOptions::default(),
         }
     }
 
-    pub fn name(&self) -> &'static str {
-        self.name
+    /// Register as benchmark.
+    pub fn bench(mut self) -> Self {
+        self.options.kind = TestKind::Bench;
+        self
     }
 
-    pub fn into_future(self) -> Pin<Box<dyn Future<Output = ()> + 'static>> {
-        self.fut
+    /// Register as setup hook.
+    pub fn setup(mut self) -> Self {
+        self.options.kind = TestKind::Setup;
+        self
     }
-}
 
-fn extract_test_context(ptr: *const u8, index: u32) -> ContextFuture {
-    unsafe {
-        let tests = read_tests(ptr);
-        let test_by_index = tests[index as usize];
-        test_by_index();
+    /// Register as teardown hook.
+    pub fn teardown(mut self) -> Self {
+        self.options.kind = TestKind::Teardown;
+        self
+    }
+
+    /// Register as hook executed once before the first test.
+    pub fn before_all(mut self) -> Self {
+        self.options.kind = TestKind::BeforeAll;
+        self
+    }
+
+    /// Register as hook executed once after the last test.
+    pub fn after_all(mut self) -> Self {
+        self.options.kind = TestKind::AfterAll;
+        self
+    }
+
+    /// Expect test to fail (optionally with failure hint containing `expected`).
+    pub fn should_fail(mut self, expected: Option<&'static str>) -> Self {
+        self.options.should_fail = Some(expected);
+        self
+    }
 
-        let test_future = core::mem::replace(&mut CONTEXT_FUTURES, Vec::new()).remove(0);
+    /// Skip test (optionally with the reason reported).
+    pub fn ignore(mut self, reason: Option<&'static str>) -> Self {
+        self.options.ignore = Some(reason);
+        self
+    }
+
+    /// Fail test if it burns more than `budget` gas.
+    pub fn gas_limit(mut self, budget: u64) -> Self {
+        self.options.gas_limit = Some(budget);
+        self
+    }
 
-        test_future
+    /// Execute failed test again up to `retries` times.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.options.retries = retries;
+        self
+    }
+
+    pub fn tags(mut self, tags: &'static [&'static str]) -> Self {
+        self.options.tags = tags;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn options(&self) -> &TestOptions {
+        &self.options
+    }
+
+    pub fn into_future(self) -> Pin<Box<dyn Future<Output = ()> + 'static>> {
+        self.fut
     }
 }
 
-fn extract_test_names(ptr: *const u8) -> Vec<&'static str> {
+fn extract_contexts(ptr: *const u8) -> Vec<ContextFuture> {
     unsafe {
         let tests = read_tests(ptr);
         for test in tests {
             test()
         }
         core::mem::replace(&mut CONTEXT_FUTURES, Vec::new())
-            .into_iter()
-            .map(|con_fut| con_fut.name())
-            .collect()
     }
 }
 
+fn extract_test_list(ptr: *const u8) -> Vec<(Cow<'static, str>, TestOptions)> {
+    extract_contexts(ptr)
+        .into_iter()
+        .map(|con_fut| (con_fut.name, con_fut.options))
+        .collect()
+}
+
 // thread-local-like variable for run_tests workflow (synchronously populating one big future)
 pub static mut CONTEXT_FUTURES: Vec<ContextFuture> = Vec::new();
 
+/// Test executed by the `WrapExecute` / `WrapCase` message.
+#[derive(Debug, Clone)]
+struct CurrentTest {
+    message_id: MessageId,
+    session_id: MessageId,
+    index: u32,
+    name: String,
+}
+
+// tests executed by `WrapExecute` / `WrapCase` messages in progress, looked up by the message
+// id, since concurrently executed tests interleave while waiting for replies
+static mut CURRENT_TESTS: Vec<CurrentTest> = Vec::new();
+
+fn find_current_test() -> CurrentTest {
+    let message_id = msg::id();
+    unsafe { CURRENT_TESTS.iter() }
+        .find(|test| test.message_id == message_id)
+        .cloned()
+        .expect("Failed to find current test")
+}
+
+pub(crate) fn current_test() -> (MessageId, u32) {
+    let test = find_current_test();
+    (test.session_id, test.index)
+}
+
+pub(crate) fn current_test_name() -> String {
+    find_current_test().name
+}
+
+// lines logged by tests, by the session and the test index, until reported by the test loop
+static mut LOGS: Vec<(MessageId, u32, String)> = Vec::new();
+
+/// Keep the line logged by the current test until the test loop reports it.
+pub(crate) fn stash_log(line: String) {
+    let (session_id, index) = current_test();
+    unsafe {
+        LOGS.push((session_id, index, line));
+    }
+}
+
+/// Lines logged by the current test so far (none outside of tests).
+pub(crate) fn current_logs() -> Vec<String> {
+    let message_id = msg::id();
+    let Some(test) = unsafe { CURRENT_TESTS.iter() }.find(|test| test.message_id == message_id)
+    else {
+        return Vec::new();
+    };
+
+    unsafe { LOGS.iter() }
+        .filter(|(session_id, index, _)| *session_id == test.session_id && *index == test.index)
+        .map(|(_, _, line)| line.clone())
+        .collect()
+}
+
+/// Lines logged by the test, removed from the stash.
+fn take_logs(session_id: &MessageId, test_index: u32) -> Vec<String> {
+    let (logs, rest): (Vec<_>, Vec<_>) = unsafe { core::mem::take(&mut LOGS) }
+        .into_iter()
+        .partition(|(session, index, _)| session == session_id && *index == test_index);
+    unsafe {
+        LOGS = rest;
+    }
+
+    logs.into_iter().map(|(_, _, line)| line).collect()
+}
+
+/// Forget tests of the session, including the ones left by panicked messages.
+pub(crate) fn drop_session(session_id: &MessageId) {
+    unsafe {
+        CURRENT_TESTS.retain(|test| test.session_id != *session_id);
+        LOGS.retain(|(session, _, _)| session != session_id);
+    }
+}
+
+/// Gas kept by the test loop when executing a single test, so that the outcome
+/// can still be reported if the test burns everything it was given.
+pub(crate) const REPORT_GAS_RESERVE: u64 = 1_000_000_000;
+
+/// Reason of the test failure.
+#[derive(Debug)]
+enum Failure {
+    Hint(String),
+    /// Failed `gear_test_runtime::assert*!`
+    Assertion(Assertion),
+}
+
+impl Failure {
+    /// Reason of the test failure out of the `WrapExecute` error reply.
+    ///
+    /// Panic inside of the test only terminates the `WrapExecute` message, panic handler of
+    /// `gstd` puts the panic message into the error reply payload, which is extracted here.
+    fn from_error(error: &Error) -> Self {
+        match error {
+            Error::ErrorReply(
+                _,
+                ErrorReplyReason::Execution(SimpleExecutionError::RanOutOfGas),
+            ) => Failure::Hint("out of gas".to_string()),
+            Error::ErrorReply(
+                payload,
+                ErrorReplyReason::Execution(SimpleExecutionError::UserspacePanic),
+            ) if !payload.0.is_empty() => {
+                let message = String::from_utf8_lossy(&payload.0);
+                match assertions::extract(&message) {
+                    Some((assertion, _)) => Failure::Assertion(assertion),
+                    None => Failure::Hint(message.into_owned()),
+                }
+            }
+            e => Failure::Hint(gstd::string::ToString::to_string(e)),
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Hint(hint) => f.write_str(hint),
+            Failure::Assertion(assertion) => fmt::Display::fmt(assertion, f),
+        }
+    }
+}
+
+/// Report lines logged by the test (or hook) executed by the finished `WrapExecute` message.
+///
+/// Lines logged since the last wait of the failed message are reverted along with the rest of
+/// its memory, failed assertion carries all the lines in the error reply instead.
+fn report_logs(
+    session: &sessions::Session,
+    session_id: &MessageId,
+    test_index: u32,
+    test_name: &str,
+    result: &Result<Vec<u8>, Error>,
+) {
+    let stashed = take_logs(session_id, test_index);
+    let logs = match result {
+        Err(Error::ErrorReply(
+            payload,
+            ErrorReplyReason::Execution(SimpleExecutionError::UserspacePanic),
+        )) => assertions::extract(&String::from_utf8_lossy(&payload.0))
+            .map(|(_, logs)| logs)
+            .unwrap_or(stashed),
+        _ => stashed,
+    };
+
+    for line in logs {
+        session.test_log(test_index, test_name, line);
+    }
+}
+
+/// Readable reason of the test failure out of the `WrapExecute` error reply.
+pub(crate) fn failure_hint(error: &Error) -> String {
+    Failure::from_error(error).to_string()
+}
+
+/// Execute test (or hook) in a separate message, so that the panic is caught.
+///
+/// Message is given all the gas available (but `REPORT_GAS_RESERVE`), up to `gas_limit`.
+async fn wrap_execute(
+    session_id: &MessageId,
+    test_index: u32,
+    gas_limit: u64,
+) -> Result<Vec<u8>, Error> {
+    msg::send_with_gas_for_reply(
+        exec::program_id(),
+        ControlSignal::WrapExecute(session_id.clone(), test_index),
+        exec::gas_available()
+            .saturating_sub(REPORT_GAS_RESERVE)
+            .min(gas_limit),
+        0,
+        0,
+    )
+    .expect("Failed to send message")
+    .await
+}
+
+/// Execute teardown hooks of the failed test in a separate message, since the panic of the
+/// test also terminated its teardown.
+///
+/// Message is given gas the same way as by `wrap_execute`. Failure of the teardown does not
+/// change the outcome of the test.
+async fn wrap_teardown(session_id: &MessageId, test_index: u32, gas_limit: u64) {
+    let result = msg::send_with_gas_for_reply(
+        exec::program_id(),
+        ControlSignal::WrapTeardown(session_id.clone(), test_index),
+        exec::gas_available()
+            .saturating_sub(REPORT_GAS_RESERVE)
+            .min(gas_limit),
+        0,
+        0,
+    )
+    .expect("Failed to send message")
+    .await;
+
+    if let Err(e) = result {
+        gstd::debug!("teardown of the failed test {} failed: {}", test_index, e);
+    }
+}
+
+/// Execute suite hooks of the given kind, reporting failures.
+///
+/// Returns `false` if any of the hooks failed, rest of the hooks is not executed then.
+async fn run_suite_hooks(
+    session_id: &MessageId,
+    session: &sessions::Session,
+    test_list: &[(Cow<'static, str>, TestOptions)],
+    kind: TestKind,
+) -> bool {
+    for (hook_index, (hook_name, hook_options)) in test_list.iter().enumerate() {
+        if hook_options.kind != kind {
+            continue;
+        }
+
+        let result = wrap_execute(session_id, hook_index as u32, u64::MAX).await;
+        report_logs(session, session_id, hook_index as u32, hook_name, &result);
+
+        if let Err(e) = result {
+            session.suite_setup_failed(hook_index as u32, hook_name, failure_hint(&e));
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Test verdict out of the `WrapExecute` result, `Ok` contains gas burned by the test
+/// (`attempt_gas` burned by the whole attempt if the test failed as expected), `Err`
+/// contains the failure reason.
+fn test_outcome(
+    result: Result<Vec<u8>, Error>,
+    options: &TestOptions,
+    attempt_gas: u64,
+) -> Result<u64, Failure> {
+    match (result, options.should_fail) {
+        (Ok(reply), None) => u64::decode(&mut &reply[..])
+            .map_err(|_| Failure::Hint("failed to decode test execution reply".to_string())),
+        (Err(e), None) => Err(Failure::from_error(&e)),
+        (Ok(_), Some(_)) => Err(Failure::Hint(
+            "test was expected to fail, but succeeded".to_string(),
+        )),
+        (Err(e), Some(Some(expected))) => {
+            let hint = failure_hint(&e);
+            if hint.contains(expected) {
+                Ok(attempt_gas)
+            } else {
+                Err(Failure::Hint(format!(
+                    "test failed as expected, but the failure hint does not contain '{}': {}",
+                    expected, hint
+                )))
+            }
+        }
+        (Err(_), Some(None)) => Ok(attempt_gas),
+    }
+}
+
+/// Contribution of the single test into the totals of the run.
+enum Verdict {
+    /// Hook or test not selected by the filter, not reported at all
+    Excluded,
+    Skipped,
+    /// Passed test (or benchmark) along with gas burned
+    Passed(u64),
+    /// Failed test along with gas burned
+    Failed(u64),
+}
+
+/// Gas given to the single attempt: everything for sequential runs, equal share of what is
+/// available for concurrent ones.
+fn attempt_gas_limit(mode: RunMode) -> u64 {
+    match mode {
+        RunMode::Sequential => u64::MAX,
+        RunMode::Concurrent(limit) => {
+            exec::gas_available().saturating_sub(REPORT_GAS_RESERVE) / limit.max(1) as u64
+        }
+    }
+}
+
+/// Tests of the single `ControlSignal::Test` run.
+struct Run<'a> {
+    session_id: &'a MessageId,
+    session: &'a sessions::Session,
+    test_list: &'a [(Cow<'static, str>, TestOptions)],
+    filter: Option<&'a TestFilter>,
+    /// `false` if `before_all` hook failed, tests are skipped then
+    suite_ready: bool,
+    /// Upper bound of gas given to the single attempt
+    attempt_gas_limit: u64,
+}
+
+impl Run<'_> {
+    /// Execute the test (retrying if declared) and report the outcome to the control bus.
+    async fn test(&self, test_index: u32) -> Verdict {
+        let session = self.session;
+        let (ref test_name, ref test_options) = self.test_list[test_index as usize];
+        if test_options.kind.is_hook() {
+            return Verdict::Excluded;
+        }
+
+        if let Some(filter) = self.filter {
+            if !filter.matches(test_name, test_options.tags) {
+                return Verdict::Excluded;
+            }
+        }
+
+        if let Some(reason) = test_options.ignore {
+            session.test_skipped(
+                test_index,
+                test_name,
+                reason.unwrap_or_default().to_string(),
+            );
+            return Verdict::Skipped;
+        }
+
+        if !self.suite_ready {
+            session.test_skipped(test_index, test_name, "suite setup failed".to_string());
+            return Verdict::Skipped;
+        }
+
+        session.test_start(test_index, test_name);
+
+        let mut attempt = 0;
+        let (outcome, attempt_gas) = loop {
+            attempt += 1;
+
+            // gas not burned by the attempt is returned once it is finished, so this also
+            // covers attempts that panicked in the middle
+            let gas_before = exec::gas_available();
+            let test_result =
+                wrap_execute(self.session_id, test_index, self.attempt_gas_limit).await;
+            let attempt_gas = gas_before.saturating_sub(exec::gas_available());
+            report_logs(
+                session,
+                self.session_id,
+                test_index,
+                test_name,
+                &test_result,
+            );
+
+            if test_result.is_err() {
+                wrap_teardown(self.session_id, test_index, self.attempt_gas_limit).await;
+            }
+
+            match test_outcome(test_result, test_options, attempt_gas) {
+                Err(failure) if attempt <= test_options.retries => {
+                    session.test_retry(test_index, test_name, attempt, failure.to_string());
+                }
+                outcome => break (outcome, attempt_gas),
+            }
+        };
+
+        match outcome {
+            Ok(gas) if test_options.gas_limit.is_some_and(|budget| gas > budget) => {
+                session.test_gas_budget_exceeded(
+                    test_index,
+                    test_name,
+                    test_options.gas_limit.unwrap_or_default(),
+                    gas,
+                );
+                Verdict::Failed(gas)
+            }
+            Ok(gas) if test_options.kind == TestKind::Bench => {
+                session.test_bench(test_index, test_name, gas);
+                Verdict::Passed(gas)
+            }
+            Ok(gas) => {
+                session.test_success(test_index, test_name, gas);
+                Verdict::Passed(gas)
+            }
+            Err(Failure::Assertion(assertion)) => {
+                session.test_assertion_failed(test_index, test_name, assertion, attempt_gas);
+                Verdict::Failed(attempt_gas)
+            }
+            Err(Failure::Hint(hint)) => {
+                session.test_fail(test_index, test_name, hint, attempt_gas);
+                Verdict::Failed(attempt_gas)
+            }
+        }
+    }
+}
+
 pub fn run_tests(ptr: *const u8) {
     // at the moment, just runs all tests
 
     gstd::message_loop(async move {
+        if mocks::is_interposer() {
+            mocks::handle().await;
+            return;
+        }
+
+        // value transfer (e.g. to the wallet, see `Session::wallet`), the value is just kept
+        if msg::size() == 0 {
+            return;
+        }
+
         // invoke all declared tests..
         let signal = ControlSignal::current();
         match signal {
             ControlSignal::Test {
                 code_hash,
                 control_bus,
+                codes,
+                filter,
+                seed,
+                mode,
+                interposer_code,
+                fixtures,
             } => {
-                let me = gstd::exec::program_id();
-                let (session_id, active_session) =
-                    sessions::new_session(code_hash, control_bus).await;
-
-                let test_names = extract_test_names(ptr);
-                let test_count = test_names.len() as u32;
-
-                for test_index in 0..test_count {
-                    // running tests synchronously
-
-                    let test_name = test_names[test_index as usize];
-                    active_session.test_start(test_index, test_name);
-
-                    let test_result = msg::send_for_reply(
-                        me,
-                        ControlSignal::WrapExecute(session_id.clone(), test_index as u32),
-                        0,
-                        0,
-                    )
-                    .expect("Failed to send message")
-                    .await;
-
-                    match test_result {
-                        Ok(_) => {
-                            active_session.test_success(test_index, test_name);
+                let (session_id, active_session) = sessions::new_session(
+                    code_hash,
+                    control_bus,
+                    codes,
+                    seed,
+                    interposer_code,
+                    fixtures,
+                )
+                .await;
+
+                let test_list = extract_test_list(ptr);
+
+                let suite_ready = run_suite_hooks(
+                    &session_id,
+                    &active_session,
+                    &test_list,
+                    TestKind::BeforeAll,
+                )
+                .await;
+
+                let run = Run {
+                    session_id: &session_id,
+                    session: &active_session,
+                    test_list: &test_list,
+                    filter: filter.as_ref(),
+                    suite_ready,
+                    attempt_gas_limit: attempt_gas_limit(mode),
+                };
+                let test_indices = 0..test_list.len() as u32;
+                let verdicts = match mode {
+                    RunMode::Sequential => {
+                        let mut verdicts = Vec::new();
+                        for test_index in test_indices {
+                            verdicts.push(run.test(test_index).await);
+                        }
+                        verdicts
+                    }
+                    RunMode::Concurrent(limit) => {
+                        stream::iter(test_indices)
+                            .map(|test_index| run.test(test_index))
+                            .buffer_unordered(limit.max(1) as usize)
+                            .collect::<Vec<_>>()
+                            .await
+                    }
+                };
+
+                // totals of the run, reported once all tests are finished
+                let (mut passed, mut failed, mut skipped, mut gas_total) = (0, 0, 0, 0);
+                for verdict in verdicts {
+                    match verdict {
+                        Verdict::Excluded => {}
+                        Verdict::Skipped => skipped += 1,
+                        Verdict::Passed(gas) => {
+                            passed += 1;
+                            gas_total += gas;
                         }
-                        Err(e) => {
-                            active_session.test_fail(
-                                test_index,
-                                test_name,
-                                gstd::string::ToString::to_string(&e),
-                            );
+                        Verdict::Failed(gas) => {
+                            failed += 1;
+                            gas_total += gas;
                         }
                     }
                 }
 
+                // executed even if the suite setup failed, to release what was acquired
+                run_suite_hooks(&session_id, &active_session, &test_list, TestKind::AfterAll).await;
+
+                active_session.suite_finished(passed, failed, skipped, gas_total);
+
                 sessions::drop_session(&session_id).await;
 
                 msg::reply((), 0).expect("Failed to reply in test path");
             }
-            ControlSignal::WrapExecute(session_id, test_index) => {
-                sessions::set_active_session(&session_id).await;
-
-                // TODO: make sure it is obvious that only one is used?
-                let test_future = extract_test_context(ptr, test_index);
-                test_future.into_future().await;
+            ControlSignal::ListTests { filter } => {
+                let tests = extract_test_list(ptr)
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, (name, options))| {
+                        !options.kind.is_hook()
+                            && filter
+                                .as_ref()
+                                .map_or(true, |filter| filter.matches(name, options.tags))
+                    })
+                    .map(|(index, (name, options))| TestDescription {
+                        index: index as u32,
+                        name: name.into_owned(),
+                        bench: options.kind == TestKind::Bench,
+                        ignored: options.ignore.is_some(),
+                        tags: options.tags.iter().map(|tag| tag.to_string()).collect(),
+                    })
+                    .collect::<Vec<_>>();
 
-                msg::reply((), 0).expect("Failed to reply in wrap execute path");
+                msg::reply(tests, 0).expect("Failed to reply in list tests path");
+            }
+            ControlSignal::WrapExecute(session_id, test_index) => {
+                execute(ptr, session_id, test_index, true).await;
+            }
+            ControlSignal::WrapCase(session_id, test_index, input) => {
+                // hooks are already executed by the `WrapExecute` of the property test
+                proptest::set_case_input(input);
+                execute(ptr, session_id, test_index, false).await;
+            }
+            ControlSignal::WrapTeardown(session_id, test_index) => {
+                teardown(ptr, session_id, test_index).await;
+            }
+            ControlSignal::Interpose(target) => {
+                mocks::interpose(target);
+            }
+            ControlSignal::Balance => {
+                msg::reply(exec::value_available(), 0).expect("Failed to reply in balance path");
+            }
+            ControlSignal::Stub { .. } => {
+                panic!("Stubs can only be declared for interposers");
             }
         };
     });
 }
+
+/// Register the message as executing the test, returning the test along with its setup and
+/// teardown hooks.
+async fn enter(
+    ptr: *const u8,
+    session_id: MessageId,
+    test_index: u32,
+) -> (ContextFuture, Vec<ContextFuture>, Vec<ContextFuture>) {
+    sessions::set_active_session(&session_id).await;
+
+    let mut contexts = extract_contexts(ptr);
+    let test_future = contexts.remove(test_index as usize);
+    unsafe {
+        CURRENT_TESTS.push(CurrentTest {
+            message_id: msg::id(),
+            session_id,
+            index: test_index,
+            name: test_future.name().to_string(),
+        });
+    }
+    if test_future.options().kind.is_suite_hook() {
+        contexts.clear();
+    }
+    let (setup_hooks, teardown_hooks) = contexts
+        .into_iter()
+        .filter(|context| matches!(context.options().kind, TestKind::Setup | TestKind::Teardown))
+        .partition(|context| context.options().kind == TestKind::Setup);
+
+    (test_future, setup_hooks, teardown_hooks)
+}
+
+/// Forget the test executed by the message.
+fn leave() {
+    let message_id = msg::id();
+    unsafe {
+        CURRENT_TESTS.retain(|test| test.message_id != message_id);
+    }
+}
+
+/// Execute single test (along with the setup and teardown hooks, if `with_hooks`), replying
+/// with gas burned by the test.
+async fn execute(ptr: *const u8, session_id: MessageId, test_index: u32, with_hooks: bool) {
+    let (test_future, mut setup_hooks, mut teardown_hooks) =
+        enter(ptr, session_id, test_index).await;
+    if !with_hooks {
+        setup_hooks.clear();
+        teardown_hooks.clear();
+    }
+
+    for hook in setup_hooks {
+        hook.into_future().await;
+    }
+
+    let gas_before = exec::gas_available();
+    test_future.into_future().await;
+    let gas_burned = gas_before.saturating_sub(exec::gas_available());
+
+    // not reached if the test panics, teardown of the failed test is executed by the
+    // `WrapTeardown` message then
+    for hook in teardown_hooks {
+        hook.into_future().await;
+    }
+
+    leave();
+
+    msg::reply(gas_burned, 0).expect("Failed to reply in wrap execute path");
+}
+
+/// Execute teardown hooks of the test which failed in the `WrapExecute` message (cases of the
+/// property test are executed without hooks).
+async fn teardown(ptr: *const u8, session_id: MessageId, test_index: u32) {
+    let (_, _, teardown_hooks) = enter(ptr, session_id, test_index).await;
+
+    for hook in teardown_hooks {
+        hook.into_future().await;
+    }
+
+    leave();
+
+    msg::reply((), 0).expect("Failed to reply in wrap teardown path");
+}
@@ -6,23 +6,83 @@
 //! In your build, include wasm-test-extractor::PreProcessor in build.rs
 //!
 //! Compatible only with gstd::async_main entry point, no custom `unsafe handle`-s please!
+//!
+//! Every test is executed in a separate message, so a panicking test is reported as failed
+//! (with the panic message as a hint) and the rest of the tests still run. Failures of
+//! `gear_test_runtime::assert!`, `assert_eq!` and `assert_ne!` are reported in a structured
+//! form, with the expression, location, expected and actual values.
 
 #![no_std]
 
+extern crate alloc;
+
 use gstd::{prelude::*, ActorId, CodeId, MessageId};
 
+pub mod assertions;
+pub mod fixtures;
 mod includes;
+pub mod manifest;
+pub mod mocks;
+pub mod proptest;
 mod sessions;
 
-pub use includes::{ContextFuture, TestResult, CONTEXT_FUTURES};
-pub use sessions::{active_session, SessionData};
+pub use includes::{ContextFuture, TestKind, TestOptions, TestResult, CONTEXT_FUTURES};
+pub use mocks::Mock;
+#[allow(deprecated)]
+pub use sessions::SessionData;
+pub use sessions::{active_session, Session};
 
 #[derive(Debug, codec::Encode, codec::Decode)]
 pub enum TestUpdate {
     Start,
-    Success,
-    /// contains information about panic / error happened
-    Fail(String),
+    /// Test succeeded, contains gas burned by its body
+    Success {
+        gas_used: u64,
+    },
+    /// contains information about panic / error happened, along with gas burned by the
+    /// failed attempt
+    Fail {
+        hint: String,
+        gas_used: u64,
+    },
+    /// Benchmark finished, contains gas burned by its body
+    BenchResult {
+        gas: u64,
+    },
+    /// Test is ignored and was not executed, contains the reason (can be empty)
+    Skipped(String),
+    /// Test succeeded, but burned more gas than its declared budget
+    GasBudgetExceeded {
+        budget: u64,
+        used: u64,
+    },
+    /// Attempt (counting from 1) failed and the test is going to be executed again
+    Retry {
+        attempt: u32,
+        hint: String,
+    },
+    /// Test failed on `gear_test_runtime::assert*!`, along with gas burned by the failed
+    /// attempt
+    AssertionFailed {
+        assertion: assertions::Assertion,
+        gas_used: u64,
+    },
+    /// Suite hook (`before_all` / `after_all`) failed, contains the failure hint.
+    ///
+    /// Tests are reported as skipped if `before_all` hook fails.
+    SuiteSetupFailed(String),
+    /// Line logged by the test (`gear_test_runtime::log!`), does not change its state
+    Log(String),
+    /// All tests are finished, contains totals of the run (benchmarks are counted as passed)
+    /// to check that no signal was lost.
+    ///
+    /// Sent once per run, with zero index and empty name.
+    SuiteFinished {
+        passed: u32,
+        failed: u32,
+        skipped: u32,
+        gas_total: u64,
+    },
 }
 
 #[derive(Debug, codec::Encode, codec::Decode)]
@@ -45,41 +105,220 @@ impl ProgressSignal {
         }
     }
 
-    pub fn success(self) -> Self {
+    pub fn success(self, gas_used: u64) -> Self {
+        let test_info = self.test_info;
+
+        ProgressSignal {
+            test_info,
+            update: TestUpdate::Success { gas_used },
+        }
+    }
+
+    pub fn fail(self, hint: String, gas_used: u64) -> Self {
+        let test_info = self.test_info;
+
+        ProgressSignal {
+            test_info,
+            update: TestUpdate::Fail { hint, gas_used },
+        }
+    }
+
+    pub fn skipped(self, reason: String) -> Self {
         let test_info = self.test_info;
 
         ProgressSignal {
             test_info,
-            update: TestUpdate::Success,
+            update: TestUpdate::Skipped(reason),
         }
     }
 
-    pub fn fail(self, hint: String) -> Self {
+    pub fn gas_budget_exceeded(self, budget: u64, used: u64) -> Self {
         let test_info = self.test_info;
 
         ProgressSignal {
             test_info,
-            update: TestUpdate::Fail(hint),
+            update: TestUpdate::GasBudgetExceeded { budget, used },
         }
     }
+
+    pub fn retry(self, attempt: u32, hint: String) -> Self {
+        let test_info = self.test_info;
+
+        ProgressSignal {
+            test_info,
+            update: TestUpdate::Retry { attempt, hint },
+        }
+    }
+
+    pub fn assertion_failed(self, assertion: assertions::Assertion, gas_used: u64) -> Self {
+        let test_info = self.test_info;
+
+        ProgressSignal {
+            test_info,
+            update: TestUpdate::AssertionFailed {
+                assertion,
+                gas_used,
+            },
+        }
+    }
+
+    pub fn suite_setup_failed(self, hint: String) -> Self {
+        let test_info = self.test_info;
+
+        ProgressSignal {
+            test_info,
+            update: TestUpdate::SuiteSetupFailed(hint),
+        }
+    }
+
+    pub fn bench_result(self, gas: u64) -> Self {
+        let test_info = self.test_info;
+
+        ProgressSignal {
+            test_info,
+            update: TestUpdate::BenchResult { gas },
+        }
+    }
+
+    pub fn suite_finished(self, passed: u32, failed: u32, skipped: u32, gas_total: u64) -> Self {
+        let test_info = self.test_info;
+
+        ProgressSignal {
+            test_info,
+            update: TestUpdate::SuiteFinished {
+                passed,
+                failed,
+                skipped,
+                gas_total,
+            },
+        }
+    }
+
+    pub fn log(self, line: String) -> Self {
+        let test_info = self.test_info;
+
+        ProgressSignal {
+            test_info,
+            update: TestUpdate::Log(line),
+        }
+    }
+}
+
+/// Selection of tests to run.
+#[derive(Debug, Clone, codec::Decode, codec::Encode)]
+pub enum TestFilter {
+    /// Tests with full name containing the given text
+    Contains(String),
+    /// Tests with exactly the given full names
+    Names(Vec<String>),
+    /// Tests having any of `include` tags (or any test if `include` is empty)
+    /// and none of `exclude` tags
+    Tags {
+        include: Vec<String>,
+        exclude: Vec<String>,
+    },
+    /// Tests matching all of the filters
+    All(Vec<TestFilter>),
+}
+
+impl TestFilter {
+    pub fn matches(&self, name: &str, tags: &[&str]) -> bool {
+        let has_tag = |list: &Vec<String>| list.iter().any(|tag| tags.contains(&tag.as_str()));
+
+        match self {
+            TestFilter::Contains(text) => name.contains(text.as_str()),
+            TestFilter::Names(names) => names.iter().any(|n| n == name),
+            TestFilter::Tags { include, exclude } => {
+                (include.is_empty() || has_tag(include)) && !has_tag(exclude)
+            }
+            TestFilter::All(filters) => filters.iter().all(|filter| filter.matches(name, tags)),
+        }
+    }
+}
+
+/// How tests of the run are executed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, codec::Decode, codec::Encode)]
+pub enum RunMode {
+    /// One test at a time, so tests using the same program do not interfere
+    #[default]
+    Sequential,
+    /// Up to the given number of tests at a time, each given an equal share of gas.
+    ///
+    /// Gas of the failed tests is measured by the test loop, so it includes gas burned by the
+    /// tests executed at the same time.
+    Concurrent(u32),
+}
+
+/// Registered test, as listed by `ControlSignal::ListTests`.
+#[derive(Debug, Clone, PartialEq, Eq, codec::Decode, codec::Encode)]
+pub struct TestDescription {
+    /// Same index the runtime reports in progress signals
+    pub index: u32,
+    pub name: String,
+    pub bench: bool,
+    pub ignored: bool,
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, codec::Decode, codec::Encode)]
 pub enum ControlSignal {
-    /// Run all tests.
+    /// Run tests (all of them if no filter provided).
     ///
     /// The only action can be called externally.
-    ///
-    /// TODO: add test filter
     Test {
         code_hash: CodeId,
         control_bus: ActorId,
+        /// Auxiliary codes tests can deploy by name
+        codes: Vec<(String, CodeId)>,
+        filter: Option<TestFilter>,
+        /// Seed of the generated inputs (see `proptest` and `Session::rng`), the same seed
+        /// reproduces the run
+        seed: u64,
+        mode: RunMode,
+        /// Code of the test program itself, deployed as interposers (see `mocks`) and wallets
+        /// (see `Session::wallet`)
+        interposer_code: CodeId,
+        /// Encoded values tests can read by name (see `Session::host_fixture`)
+        fixtures: Vec<(String, Vec<u8>)>,
     },
 
+    /// List tests (all of them if no filter provided) without running anything.
+    ///
+    /// Replies with the encoded `Vec<TestDescription>`, hooks are not listed.
+    ListTests { filter: Option<TestFilter> },
+
     /// Execute single test to try catch panic if any.
     ///
-    /// Can only be called internally by this actor.
+    /// Can only be called internally by this actor, replies with gas burned by the test.
     WrapExecute(MessageId, u32),
+
+    /// Execute single case of the property test with the encoded input, without the setup and
+    /// teardown hooks.
+    ///
+    /// Can only be called internally by this actor, replies as `WrapExecute`.
+    WrapCase(MessageId, u32, Vec<u8>),
+
+    /// Execute teardown hooks of the test which failed in `WrapExecute`.
+    ///
+    /// Can only be called internally by this actor.
+    WrapTeardown(MessageId, u32),
+
+    /// Turn this (freshly created) program into the interposer in front of the given actor.
+    ///
+    /// Sent by `Session::interpose` right after creating the interposer, the sender becomes its
+    /// owner. Rejected by the program which already ran tests.
+    Interpose(ActorId),
+
+    /// Reply with the balance of this program (`exec::value_available()`), since programs can
+    /// not query balances of other actors.
+    ///
+    /// Answered by the wallets (see `Session::wallet`) and the test program itself.
+    Balance,
+
+    /// Answer the encoded request with the encoded reply instead of forwarding it.
+    ///
+    /// Can only be sent to the interposer by its owner (see `Mock::stub`).
+    Stub { request: Vec<u8>, reply: Vec<u8> },
 }
 
 impl ControlSignal {
@@ -0,0 +1,77 @@
+//! Records of the test manifest, placed by the codegen macros into the `gtest-manifest` custom
+//! section of the wasm, so that tests can be listed without executing anything.
+//!
+//! Linker concatenates records of all tests into the single section. Record layout (strings
+//! are prefixed with the little-endian `u32` length):
+//!
+//! - export name of the entry (`test_*`)
+//! - full name of the test
+//! - kind (`TestKind as u8`)
+//! - doc comment (can be empty)
+//! - number of tags (little-endian `u32`), followed by the tags
+
+/// Name of the custom section.
+pub const SECTION: &str = "gtest-manifest";
+
+/// Size of the record, to declare the array holding it.
+pub const fn record_len(export: &str, name: &str, doc: &str, tags: &[&str]) -> usize {
+    let mut len = 4 + export.len() + 4 + name.len() + 1 + 4 + doc.len() + 4;
+
+    let mut i = 0;
+    while i < tags.len() {
+        len += 4 + tags[i].len();
+        i += 1;
+    }
+
+    len
+}
+
+/// Encoded record, `N` should be `record_len` of the same arguments.
+pub const fn record<const N: usize>(
+    export: &str,
+    name: &str,
+    kind: u8,
+    doc: &str,
+    tags: &[&str],
+) -> [u8; N] {
+    let out = [0u8; N];
+
+    let (out, pos) = put_str(out, 0, export);
+    let (mut out, pos) = put_str(out, pos, name);
+    out[pos] = kind;
+    let (out, pos) = put_str(out, pos + 1, doc);
+    let (mut out, mut pos) = put_u32(out, pos, tags.len() as u32);
+
+    let mut i = 0;
+    while i < tags.len() {
+        (out, pos) = put_str(out, pos, tags[i]);
+        i += 1;
+    }
+
+    out
+}
+
+const fn put_u32<const N: usize>(mut out: [u8; N], pos: usize, value: u32) -> ([u8; N], usize) {
+    let bytes = value.to_le_bytes();
+
+    let mut i = 0;
+    while i < 4 {
+        out[pos + i] = bytes[i];
+        i += 1;
+    }
+
+    (out, pos + 4)
+}
+
+const fn put_str<const N: usize>(out: [u8; N], pos: usize, value: &str) -> ([u8; N], usize) {
+    let (mut out, pos) = put_u32(out, pos, value.len() as u32);
+    let bytes = value.as_bytes();
+
+    let mut i = 0;
+    while i < bytes.len() {
+        out[pos + i] = bytes[i];
+        i += 1;
+    }
+
+    (out, pos + bytes.len())
+}
@@ -0,0 +1,132 @@
+//! Interposers answering stubbed requests instead of the actor behind them (see
+//! `Session::interpose`).
+//!
+//! Interposer is another instance of the test program (its code is supplied by the runner),
+//! configured by `ControlSignal::Interpose` from the test which created it. Requests matching
+//! a stub are answered with the stubbed reply, the rest is forwarded to the target along with
+//! the value, and the reply of the target is returned back.
+
+#![allow(static_mut_refs)]
+
+use codec::{Decode, Encode};
+use gstd::{msg, prelude::*, ActorId};
+
+use crate::{sessions, ControlSignal};
+
+/// Interposer in front of the `target`, as seen by the test which created it.
+#[derive(Debug, Clone)]
+pub struct Mock {
+    interposer: ActorId,
+    target: ActorId,
+}
+
+impl Mock {
+    pub(crate) fn new(interposer: ActorId, target: ActorId) -> Self {
+        Mock { interposer, target }
+    }
+
+    /// Address to use instead of the target (e.g. in the init payload of the program which
+    /// depends on it).
+    pub fn actor(&self) -> ActorId {
+        self.interposer
+    }
+
+    /// Actor behind the interposer.
+    pub fn target(&self) -> ActorId {
+        self.target
+    }
+
+    /// Answer the encoded `request` with the encoded `reply`, without forwarding it to the
+    /// target. Stubs declared later take precedence.
+    ///
+    /// Panics if the interposer does not accept the stub.
+    pub async fn stub(&self, request: impl Encode, reply: impl Encode) {
+        msg::send_for_reply(
+            self.interposer,
+            ControlSignal::Stub {
+                request: request.encode(),
+                reply: reply.encode(),
+            },
+            0,
+            0,
+        )
+        .expect("Failed to send stub")
+        .await
+        .unwrap_or_else(|e| panic!("Interposer {:?} rejected stub: {}", self.interposer, e));
+    }
+}
+
+/// Configuration of this program, if it is deployed as an interposer.
+#[derive(Debug)]
+struct Interposer {
+    /// Test which created the interposer, the only one allowed to declare stubs
+    owner: ActorId,
+    target: ActorId,
+    stubs: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+static mut INTERPOSER: Option<Interposer> = None;
+
+/// Whether this program is deployed as an interposer, so messages are not control signals.
+pub(crate) fn is_interposer() -> bool {
+    unsafe { INTERPOSER.is_some() }
+}
+
+/// Turn this program into the interposer in front of `target`.
+///
+/// Only the fresh program is turned into the interposer, the one which already ran tests
+/// rejects the request, so it can not be taken over by any sender. Messages to the interposer
+/// (including `ControlSignal::Interpose`) are handled by `handle`.
+pub(crate) fn interpose(target: ActorId) {
+    if sessions::session_started() {
+        panic!("Test program which ran tests can not be turned into the interposer");
+    }
+
+    unsafe {
+        INTERPOSER = Some(Interposer {
+            owner: msg::source(),
+            target,
+            stubs: Vec::new(),
+        });
+    }
+
+    msg::reply((), 0).expect("Failed to reply in interpose path");
+}
+
+/// Handle the message to the interposer: stub declaration from the owner or request to the
+/// target.
+pub(crate) async fn handle() {
+    let interposer = unsafe { INTERPOSER.as_mut() }.expect("Program is not an interposer");
+    let payload = msg::load_bytes().expect("Failed to load payload");
+
+    if msg::source() == interposer.owner {
+        let mut input = &payload[..];
+        if let Ok(ControlSignal::Stub { request, reply }) = ControlSignal::decode(&mut input) {
+            if input.is_empty() {
+                interposer.stubs.push((request, reply));
+                msg::reply((), 0).expect("Failed to reply in stub path");
+                return;
+            }
+        }
+    }
+
+    let stubbed = interposer
+        .stubs
+        .iter()
+        .rev()
+        .find(|(request, _)| *request == payload)
+        .map(|(_, reply)| reply.clone());
+
+    let reply = match stubbed {
+        Some(reply) => reply,
+        None => {
+            let target = interposer.target;
+            msg::send_bytes_for_reply(target, payload, msg::value(), 0)
+                .expect("Failed to forward request")
+                .await
+                .unwrap_or_else(|e| panic!("Target {:?} failed to handle request: {}", target, e))
+        }
+    };
+
+    msg::reply_bytes(reply, 0).expect("Failed to reply in interposer path");
+}
@@ -0,0 +1,313 @@
+//! Property-based testing support for `#[gear_test_codegen::proptest]`.
+//!
+//! Inputs are generated from the seed of the test run (see `Session::rng`), so the same seed
+//! always produces the same cases. Every case is executed in a separate message
+//! (`ControlSignal::WrapCase`), so a panicking case does not terminate the property test
+//! itself, which then shrinks the input and fails with the minimal counterexample found.
+//! Setup and teardown hooks are executed once for the whole property test, not per case.
+
+#![allow(static_mut_refs)]
+
+use codec::{Decode, Encode};
+use gstd::{exec, msg, prelude::*, MessageId};
+
+use crate::{includes, sessions, ControlSignal};
+
+/// Cases executed when not specified in the attribute.
+pub const DEFAULT_CASES: u32 = 32;
+
+/// Upper bound of the shrinking attempts, each attempt is a separate message.
+const MAX_SHRINK_RUNS: u32 = 256;
+
+/// Deterministic pseudo-random generator (SplitMix64).
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Value in `0..bound` (`bound` should be non-zero).
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
+
+/// Input of the property test, which can be generated and shrunk.
+pub trait Arbitrary: Encode + Decode + Clone + core::fmt::Debug + Sized {
+    fn arbitrary(rng: &mut Rng) -> Self;
+
+    /// Simpler values to try instead of this one, simplest first.
+    fn shrink(&self) -> Vec<Self> {
+        Vec::new()
+    }
+}
+
+macro_rules! impl_arbitrary_unsigned {
+    ($($t:ty),*) => {$(
+        impl Arbitrary for $t {
+            fn arbitrary(rng: &mut Rng) -> Self {
+                rng.next_u64() as $t
+            }
+
+            fn shrink(&self) -> Vec<Self> {
+                let mut candidates = vec![0, *self / 2, self.saturating_sub(1)];
+                candidates.dedup();
+                candidates.retain(|candidate| candidate != self);
+                candidates
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_arbitrary_signed {
+    ($($t:ty),*) => {$(
+        impl Arbitrary for $t {
+            fn arbitrary(rng: &mut Rng) -> Self {
+                rng.next_u64() as $t
+            }
+
+            fn shrink(&self) -> Vec<Self> {
+                let mut candidates = vec![0, *self / 2, *self - self.signum()];
+                if *self < 0 {
+                    candidates.insert(1, self.saturating_neg());
+                }
+                candidates.dedup();
+                candidates.retain(|candidate| candidate != self);
+                candidates
+            }
+        }
+    )*};
+}
+
+impl_arbitrary_unsigned!(u8, u16, u32, u64);
+impl_arbitrary_signed!(i8, i16, i32, i64);
+
+impl Arbitrary for u128 {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        ((rng.next_u64() as u128) << 64) | rng.next_u64() as u128
+    }
+
+    fn shrink(&self) -> Vec<Self> {
+        let mut candidates = vec![0, *self / 2, self.saturating_sub(1)];
+        candidates.dedup();
+        candidates.retain(|candidate| candidate != self);
+        candidates
+    }
+}
+
+impl Arbitrary for bool {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        rng.next_u64() & 1 == 1
+    }
+
+    fn shrink(&self) -> Vec<Self> {
+        if *self {
+            vec![false]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Longest generated collection.
+const MAX_LEN: u64 = 16;
+
+impl<T: Arbitrary> Arbitrary for Vec<T> {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let len = rng.below(MAX_LEN + 1);
+        (0..len).map(|_| T::arbitrary(rng)).collect()
+    }
+
+    fn shrink(&self) -> Vec<Self> {
+        let mut candidates = Vec::new();
+        if self.is_empty() {
+            return candidates;
+        }
+
+        candidates.push(Vec::new());
+        if self.len() > 1 {
+            candidates.push(self[..self.len() / 2].to_vec());
+            candidates.push(self[self.len() / 2..].to_vec());
+        }
+        for index in 0..self.len() {
+            let mut shorter = self.clone();
+            shorter.remove(index);
+            candidates.push(shorter);
+        }
+        for (index, item) in self.iter().enumerate() {
+            for simpler in item.shrink() {
+                let mut candidate = self.clone();
+                candidate[index] = simpler;
+                candidates.push(candidate);
+            }
+        }
+        candidates
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for Option<T> {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        match rng.below(4) {
+            0 => None,
+            _ => Some(T::arbitrary(rng)),
+        }
+    }
+
+    fn shrink(&self) -> Vec<Self> {
+        match self {
+            None => Vec::new(),
+            Some(value) => core::iter::once(None)
+                .chain(value.shrink().into_iter().map(Some))
+                .collect(),
+        }
+    }
+}
+
+impl Arbitrary for String {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let len = rng.below(MAX_LEN + 1);
+        (0..len)
+            .map(|_| char::from(b' ' + rng.below(95) as u8))
+            .collect()
+    }
+
+    fn shrink(&self) -> Vec<Self> {
+        let chars = self.chars().collect::<Vec<_>>();
+        let mut candidates = Vec::new();
+        if chars.is_empty() {
+            return candidates;
+        }
+
+        candidates.push(String::new());
+        for index in 0..chars.len() {
+            let mut shorter = chars.clone();
+            shorter.remove(index);
+            candidates.push(shorter.into_iter().collect());
+        }
+        candidates
+    }
+}
+
+macro_rules! impl_arbitrary_tuple {
+    ($($name:ident : $index:tt),+) => {
+        impl<$($name: Arbitrary),+> Arbitrary for ($($name,)+) {
+            fn arbitrary(rng: &mut Rng) -> Self {
+                ($($name::arbitrary(rng),)+)
+            }
+
+            fn shrink(&self) -> Vec<Self> {
+                let mut candidates = Vec::new();
+                $(
+                    for simpler in self.$index.shrink() {
+                        let mut candidate = self.clone();
+                        candidate.$index = simpler;
+                        candidates.push(candidate);
+                    }
+                )+
+                candidates
+            }
+        }
+    };
+}
+
+impl_arbitrary_tuple!(A: 0);
+impl_arbitrary_tuple!(A: 0, B: 1);
+impl_arbitrary_tuple!(A: 0, B: 1, C: 2);
+impl_arbitrary_tuple!(A: 0, B: 1, C: 2, D: 3);
+impl_arbitrary_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_arbitrary_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+
+// inputs of the cases executed by `WrapCase` messages in progress, by the message id
+static mut CASE_INPUTS: Vec<(MessageId, Vec<u8>)> = Vec::new();
+
+pub(crate) fn set_case_input(input: Vec<u8>) {
+    unsafe {
+        CASE_INPUTS.push((msg::id(), input));
+    }
+}
+
+/// Input of the case, if the property test is executed as a single case.
+pub fn case_input<T: Decode>() -> Option<T> {
+    let message_id = msg::id();
+    let position = unsafe { CASE_INPUTS.iter() }.position(|(id, _)| *id == message_id)?;
+    let (_, input) = unsafe { CASE_INPUTS.swap_remove(position) };
+
+    Some(T::decode(&mut &input[..]).expect("Failed to decode property test case"))
+}
+
+/// Execute a single case, returns the failure hint if it fails.
+async fn run_case<T: Arbitrary>(input: &T) -> Result<(), String> {
+    let (session_id, test_index) = includes::current_test();
+
+    msg::send_with_gas_for_reply(
+        exec::program_id(),
+        ControlSignal::WrapCase(session_id, test_index, input.encode()),
+        exec::gas_available().saturating_sub(includes::REPORT_GAS_RESERVE),
+        0,
+        0,
+    )
+    .expect("Failed to send message")
+    .await
+    .map(|_| ())
+    .map_err(|e| includes::failure_hint(&e))
+}
+
+/// Run `cases` generated cases of the current property test.
+///
+/// Panics with the minimal counterexample found if any case fails. Shrink runs (up to
+/// `MAX_SHRINK_RUNS`) are charged to the gas of the test along with the cases.
+pub async fn check<T: Arbitrary>(cases: u32) {
+    let session = sessions::active_session();
+    let seed = session.seed();
+    let mut rng = session.rng();
+
+    for case in 1..=cases {
+        let input = T::arbitrary(&mut rng);
+        let Err(hint) = run_case(&input).await else {
+            continue;
+        };
+
+        let (minimal, hint, shrinks) = shrink(input, hint).await;
+        panic!(
+            "property failed at case {} of {} (seed {}), shrunk {} time(s), minimal counterexample: {:?}: {}",
+            case, cases, seed, shrinks, minimal, hint
+        );
+    }
+}
+
+/// Greedily replace the failing input with the first simpler one that still fails.
+async fn shrink<T: Arbitrary>(mut input: T, mut hint: String) -> (T, String, u32) {
+    let mut runs = 0;
+    let mut shrinks = 0;
+
+    'outer: while runs < MAX_SHRINK_RUNS {
+        for candidate in input.shrink() {
+            if runs >= MAX_SHRINK_RUNS {
+                break 'outer;
+            }
+            runs += 1;
+
+            if let Err(candidate_hint) = run_case(&candidate).await {
+                input = candidate;
+                hint = candidate_hint;
+                shrinks += 1;
+                continue 'outer;
+            }
+        }
+        break;
+    }
+
+    (input, hint, shrinks)
+}
@@ -1,23 +1,262 @@
-use super::ProgressSignal;
-use gstd::{msg, prelude::*, sync::RwLock, ActorId, CodeId, MessageId};
+use super::{
+    assertions::Assertion, fixtures, includes, mocks::Mock, proptest::Rng, ControlSignal,
+    ProgressSignal,
+};
+use codec::{Decode, Encode};
+use core::future::Future;
+use gstd::{
+    exec, msg, prelude::*, prog::ProgramGenerator, sync::RwLock, ActorId, CodeId, MessageId,
+};
 
 #[derive(Debug)]
-struct Session {
+struct SessionEntry {
     init_message: MessageId,
-    data: SessionData,
+    data: Session,
 }
 
+/// Test session, passed to every test function.
 #[derive(Debug, Clone)]
-pub struct SessionData {
+pub struct Session {
     code_hash: CodeId,
     control_bus: ActorId,
+    codes: Vec<(String, CodeId)>,
+    seed: u64,
+    interposer_code: CodeId,
+    /// Encoded values supplied by the host, see `host_fixture`
+    fixtures: Vec<(String, Vec<u8>)>,
+    /// Program under test, deployed for the current test
+    actor: Option<ActorId>,
 }
 
-impl SessionData {
+/// Former name of the [`Session`].
+#[deprecated(note = "use `Session` instead")]
+pub type SessionData = Session;
+
+impl Session {
+    /// Code under test.
     pub fn testee(&self) -> CodeId {
         self.code_hash.clone()
     }
 
+    /// Control bus collecting the test progress.
+    pub fn control_bus(&self) -> ActorId {
+        self.control_bus
+    }
+
+    /// Program under test, deployed for the current test with the payload declared by the
+    /// `init` argument of the test.
+    ///
+    /// Panics if the test does not declare `init`.
+    pub fn actor(&self) -> ActorId {
+        self.actor
+            .expect("Program under test is not deployed, declare `init = <payload>` for the test")
+    }
+
+    /// Same session with the given program under test.
+    pub fn with_actor(mut self, actor: ActorId) -> Self {
+        self.actor = Some(actor);
+        self
+    }
+
+    /// Create program from the code under test, initialized with the encoded `payload`.
+    ///
+    /// Panics if initialization fails.
+    pub async fn deploy_testee(&self, payload: impl Encode) -> ActorId {
+        create(self.testee(), payload.encode(), 0, "program under test").await
+    }
+
+    /// Create program from `code`, initialized with the encoded `payload` and `value`
+    /// attached, and wait for the initialization.
+    ///
+    /// Panics if the program can not be created or its initialization fails.
+    pub async fn create_program(&self, code: CodeId, payload: impl Encode, value: u128) -> ActorId {
+        create(
+            code,
+            payload.encode(),
+            value,
+            &format!("program from code {:?}", code),
+        )
+        .await
+    }
+
+    /// Seed of the test run.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Deterministic generator for the current test, seeded from the seed of the test run
+    /// and the test index, so the same seed reproduces the same values.
+    ///
+    /// Every call starts the same sequence, keep the generator to draw different values.
+    pub fn rng(&self) -> Rng {
+        let (_, test_index) = includes::current_test();
+        Rng::new(self.seed ^ (test_index as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    }
+
+    /// Full name of the current test (of the test the hook is executed for, in hooks).
+    pub fn test_name(&self) -> String {
+        includes::current_test_name()
+    }
+
+    /// Auxiliary code supplied under the given name.
+    ///
+    /// Panics if there is no such code.
+    pub fn code(&self, name: &str) -> CodeId {
+        self.codes
+            .iter()
+            .find(|(code_name, _)| code_name == name)
+            .map(|(_, code_id)| code_id.clone())
+            .unwrap_or_else(|| panic!("Code '{}' is not supplied for the test run", name))
+    }
+
+    /// Value supplied by the host under the given name (see `ControlClient::set_fixture` of
+    /// the test runner), `None` if there is no such value.
+    ///
+    /// Panics if the value can not be decoded as `T`.
+    pub fn host_fixture<T: Decode>(&self, name: &str) -> Option<T> {
+        self.fixtures
+            .iter()
+            .find(|(fixture_name, _)| fixture_name == name)
+            .map(|(_, value)| {
+                T::decode(&mut &value[..])
+                    .unwrap_or_else(|_| panic!("Failed to decode host fixture '{}'", name))
+            })
+    }
+
+    /// Create program from the named auxiliary code with empty init payload.
+    ///
+    /// Panics if there is no such code or initialization fails.
+    pub async fn deploy(&self, name: &str) -> ActorId {
+        create(
+            self.code(name),
+            Vec::new(),
+            0,
+            &format!("program '{}'", name),
+        )
+        .await
+    }
+
+    /// Deploy the interposer in front of `target`, answering stubbed requests (see
+    /// `Mock::stub`) instead of forwarding them.
+    ///
+    /// Panics if the interposer can not be deployed.
+    pub async fn interpose(&self, target: ActorId) -> Mock {
+        let interposer = create(self.interposer_code, Vec::new(), 0, "interposer").await;
+
+        msg::send_for_reply(interposer, ControlSignal::Interpose(target), 0, 0)
+            .expect("Failed to configure interposer")
+            .await
+            .unwrap_or_else(|e| panic!("Failed to configure interposer: {}", e));
+
+        Mock::new(interposer, target)
+    }
+
+    /// Send encoded `request` (with `value` attached) to `actor` and decode its reply.
+    ///
+    /// Panics, failing the test with the request context, if the request can not be sent,
+    /// `actor` replies with an error or the reply can not be decoded as `Resp`.
+    pub async fn send<Req: Encode, Resp: Decode>(
+        &self,
+        actor: ActorId,
+        request: Req,
+        value: u128,
+    ) -> Resp {
+        self.try_send(actor, request, value)
+            .await
+            .unwrap_or_else(|e| {
+                panic!(
+                    "Request {} to {:?} failed (expected {} in reply): {}",
+                    core::any::type_name::<Req>(),
+                    actor,
+                    core::any::type_name::<Resp>(),
+                    e
+                )
+            })
+    }
+
+    /// Same as `send`, but returns the error instead of failing the test.
+    pub async fn try_send<Req: Encode, Resp: Decode>(
+        &self,
+        actor: ActorId,
+        request: Req,
+        value: u128,
+    ) -> Result<Resp, gstd::errors::Error> {
+        msg::send_for_reply_as::<_, Resp>(actor, request, value, 0)?.await
+    }
+
+    /// Send `value` to `actor` without payload (e.g. to fund an account).
+    ///
+    /// Panics if the message can not be sent.
+    pub fn transfer(&self, actor: ActorId, value: u128) {
+        msg::send_bytes(actor, [], value)
+            .unwrap_or_else(|e| panic!("Failed to transfer {} to {:?}: {}", value, actor, e));
+    }
+
+    /// Balance of the test program, the value tests can attach to messages.
+    ///
+    /// Programs can not query balances of other actors, value received by them is checked with
+    /// the wallet (see `wallet` and `balance_of`).
+    pub fn balance(&self) -> u128 {
+        exec::value_available()
+    }
+
+    /// Deploy the wallet: account keeping the value sent to it, which reports its balance to
+    /// `balance_of`.
+    ///
+    /// Panics if the wallet can not be deployed.
+    pub async fn wallet(&self) -> ActorId {
+        create(self.interposer_code, Vec::new(), 0, "wallet").await
+    }
+
+    /// Balance of the test program or the wallet (see `wallet`), asked by
+    /// `ControlSignal::Balance`.
+    ///
+    /// Panics if the actor does not report its balance.
+    pub async fn balance_of(&self, actor: ActorId) -> u128 {
+        if actor == exec::program_id() {
+            return self.balance();
+        }
+
+        msg::send_for_reply_as::<_, u128>(actor, ControlSignal::Balance, 0, 0)
+            .expect("Failed to send balance query")
+            .await
+            .unwrap_or_else(|e| {
+                panic!(
+                    "{:?} did not report its balance (only wallets do): {}",
+                    actor, e
+                )
+            })
+    }
+
+    /// Assert that the test program or the wallet (see `balance_of`) has exactly the `expected`
+    /// balance.
+    pub async fn assert_balance(&self, actor: ActorId, expected: u128) {
+        crate::assert_eq!(
+            self.balance_of(actor).await,
+            expected,
+            "balance of {:?}",
+            actor
+        );
+    }
+
+    /// Await `action` and return its output along with the change of the test program
+    /// balance (negative if value was spent).
+    ///
+    /// Not supported under `RunMode::Concurrent`: tests executed at the same time change the
+    /// balance as well, send value to the wallet and check it by `assert_balance` instead.
+    pub async fn balance_change<T>(&self, action: impl Future<Output = T>) -> (T, i128) {
+        let before = self.balance();
+        let output = action.await;
+
+        (output, self.balance() as i128 - before as i128)
+    }
+
+    /// Log the line of the current test, see `gear_test_runtime::log!`.
+    pub fn log(&self, line: String) {
+        gstd::debug!("{}: {}", includes::current_test_name(), line);
+        includes::stash_log(line);
+    }
+
     fn send_progress(&self, msg: ProgressSignal) {
         let _ = msg::send(self.control_bus, msg, 0);
     }
@@ -27,28 +266,125 @@ impl SessionData {
         self.send_progress(ProgressSignal::new(index, name.to_string()));
     }
 
-    pub fn test_success(&self, index: u32, name: &str) {
-        gstd::debug!("test success: {}", name);
-        self.send_progress(ProgressSignal::new(index, name.to_string()).success());
+    pub fn test_log(&self, index: u32, name: &str, line: String) {
+        self.send_progress(ProgressSignal::new(index, name.to_string()).log(line))
+    }
+
+    pub fn test_success(&self, index: u32, name: &str, gas_used: u64) {
+        gstd::debug!("test success: {} ({} gas)", name, gas_used);
+        self.send_progress(ProgressSignal::new(index, name.to_string()).success(gas_used));
+    }
+
+    pub fn test_fail(&self, index: u32, name: &str, hint: String, gas_used: u64) {
+        gstd::debug!("test fail: {} ({} gas)", name, gas_used);
+        self.send_progress(ProgressSignal::new(index, name.to_string()).fail(hint, gas_used))
+    }
+
+    pub fn test_assertion_failed(
+        &self,
+        index: u32,
+        name: &str,
+        assertion: Assertion,
+        gas_used: u64,
+    ) {
+        gstd::debug!("test assertion failed: {} ({} gas)", name, gas_used);
+        self.send_progress(
+            ProgressSignal::new(index, name.to_string()).assertion_failed(assertion, gas_used),
+        )
+    }
+
+    pub fn suite_setup_failed(&self, index: u32, name: &str, hint: String) {
+        gstd::debug!("suite hook failed: {}", name);
+        self.send_progress(ProgressSignal::new(index, name.to_string()).suite_setup_failed(hint))
+    }
+
+    pub fn test_skipped(&self, index: u32, name: &str, reason: String) {
+        gstd::debug!("test skipped: {}", name);
+        self.send_progress(ProgressSignal::new(index, name.to_string()).skipped(reason))
     }
 
-    pub fn test_fail(&self, index: u32, name: &str, hint: String) {
-        gstd::debug!("test fail: {}", name);
-        self.send_progress(ProgressSignal::new(index, name.to_string()).fail(hint))
+    pub fn test_gas_budget_exceeded(&self, index: u32, name: &str, budget: u64, used: u64) {
+        gstd::debug!("test exceeded gas budget: {} ({}/{})", name, used, budget);
+        self.send_progress(
+            ProgressSignal::new(index, name.to_string()).gas_budget_exceeded(budget, used),
+        )
     }
+
+    pub fn test_retry(&self, index: u32, name: &str, attempt: u32, hint: String) {
+        gstd::debug!("test attempt {} failed: {}", attempt, name);
+        self.send_progress(ProgressSignal::new(index, name.to_string()).retry(attempt, hint))
+    }
+
+    pub fn suite_finished(&self, passed: u32, failed: u32, skipped: u32, gas_total: u64) {
+        gstd::debug!(
+            "suite finished: {} passed, {} failed, {} skipped ({} gas)",
+            passed,
+            failed,
+            skipped,
+            gas_total
+        );
+        self.send_progress(
+            ProgressSignal::new(0, String::new())
+                .suite_finished(passed, failed, skipped, gas_total),
+        )
+    }
+
+    pub fn test_bench(&self, index: u32, name: &str, gas: u64) {
+        gstd::debug!("bench result: {} ({} gas)", name, gas);
+        self.send_progress(ProgressSignal::new(index, name.to_string()).bench_result(gas))
+    }
+}
+
+/// Log formatted line of the current test.
+///
+/// Lines are sent to the control bus once the test is finished (even if it fails), runner
+/// prints them under `--nocapture` (and along with the failure report otherwise), unlike
+/// `gstd::debug!` output which is not visible there.
+#[macro_export]
+macro_rules! log {
+    ($($arg:tt)+) => {
+        $crate::active_session().log($crate::assertions::message(::core::format_args!($($arg)+)))
+    };
+}
+
+/// Create program and wait for its initialization, panicking with the `what` context.
+async fn create(code: CodeId, payload: Vec<u8>, value: u128, what: &str) -> ActorId {
+    let (actor_id, _) = ProgramGenerator::create_program_bytes_for_reply(code, payload, value, 0)
+        .unwrap_or_else(|e| panic!("Failed to create {}: {}", what, e))
+        .await
+        .unwrap_or_else(|e| panic!("Failed to initialize {}: {}", what, e));
+
+    actor_id
 }
 
 // Vec is good enough if not much simultaneous sessions
-static SESSIONS: RwLock<Vec<Session>> = RwLock::new(Vec::new());
-static mut ACTIVE_SESSION: Option<SessionData> = None;
+static SESSIONS: RwLock<Vec<SessionEntry>> = RwLock::new(Vec::new());
+static mut ACTIVE_SESSION: Option<Session> = None;
+// whether this program ever ran tests, kept after the sessions are dropped
+static mut SESSION_STARTED: bool = false;
 
-pub async fn new_session(code_hash: CodeId, control_bus: ActorId) -> (MessageId, SessionData) {
-    let data = SessionData {
+pub async fn new_session(
+    code_hash: CodeId,
+    control_bus: ActorId,
+    codes: Vec<(String, CodeId)>,
+    seed: u64,
+    interposer_code: CodeId,
+    fixtures: Vec<(String, Vec<u8>)>,
+) -> (MessageId, Session) {
+    let data = Session {
         code_hash,
         control_bus,
+        codes,
+        seed,
+        interposer_code,
+        fixtures,
+        actor: None,
     };
     let init_message = msg::id();
-    SESSIONS.write().await.push(Session {
+    unsafe {
+        SESSION_STARTED = true;
+    }
+    SESSIONS.write().await.push(SessionEntry {
         init_message: init_message.clone(),
         data: data.clone(),
     });
@@ -59,7 +395,7 @@ pub async fn new_session(code_hash: CodeId, control_bus: ActorId) -> (MessageId,
 /// Locate existing session.
 ///
 /// If section is not found, panics.
-pub async fn locate_session(init_message: &MessageId) -> SessionData {
+pub async fn locate_session(init_message: &MessageId) -> Session {
     let sessions = SESSIONS.read().await;
 
     let found_session = sessions
@@ -79,9 +415,16 @@ pub async fn drop_session(init_message: &MessageId) {
         .expect("Session not found. Terminating");
 
     sessions.swap_remove(found_index);
+    fixtures::drop_session(init_message);
+    includes::drop_session(init_message);
+}
+
+/// Whether this program ever started a test session.
+pub(crate) fn session_started() -> bool {
+    unsafe { SESSION_STARTED }
 }
 
-pub fn active_session() -> SessionData {
+pub fn active_session() -> Session {
     unsafe {
         ACTIVE_SESSION
             .as_ref()